@@ -1,5 +1,4 @@
 use log::*;
-use netconf_rs;
 use netconf_rs::vendor::h3c::*;
 use netconf_rs::Connection;
 
@@ -12,8 +11,8 @@ fn main() {
     let ssh = netconf_rs::transport::ssh::SSHTransport::connect(&addr, "admin", "admin").unwrap();
     let mut conn = Connection::new(ssh).unwrap();
     conn.get_config().unwrap();
-    get_netconf_information(&mut conn).unwrap();
-    //get_schema(&mut conn, "openconfig-network-instance-l2", "2018-03-28", "yang").unwrap();
+    conn.get_netconf_state().unwrap();
+    //conn.get_schema("openconfig-network-instance-l2", Some("2018-03-28"), Some("yang")).unwrap();
     get_mac_table(&mut conn).unwrap();
     /*
     get_vlan_config(&mut conn).unwrap();
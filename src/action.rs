@@ -0,0 +1,238 @@
+//! YANG 1.1 actions
+//!
+//! An action is an operation bound to a node of the data tree
+//! ([RFC 7950, section 7.15](https://datatracker.ietf.org/doc/html/rfc7950#section-7.15)),
+//! such as resetting a particular interface. It is invoked with an `<action>`
+//! element containing the path from the top of the data tree down to the
+//! node, with the action itself as the innermost element.
+
+use crate::xml::{escape, from_str};
+use crate::Connection;
+use log::*;
+use serde::de::DeserializeOwned;
+use std::io;
+
+/// Namespace of the `<action>` operation
+pub const YANG_ACTION_NS: &str = "urn:ietf:params:xml:ns:yang:1";
+
+/// A step in the instance path of the data node an action is invoked on
+///
+/// # Example
+///
+/// ```
+/// use netconf_rs::action::PathNode;
+///
+/// let path = [
+///     PathNode::new("interfaces").namespace("urn:ietf:params:xml:ns:yang:ietf-interfaces"),
+///     PathNode::new("interface").key("name", "GigabitEthernet1/0/1"),
+/// ];
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathNode {
+    /// Name of the container or list
+    pub name: String,
+    /// Namespace of the node, inherited from the parent if `None`
+    ///
+    /// The top-level node has no parent in the data tree, so it must have one.
+    pub namespace: Option<String>,
+    /// Key leaves identifying a list entry, in schema order
+    pub keys: Vec<(String, String)>,
+}
+
+impl PathNode {
+    /// Create a path node in the namespace of its parent
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            namespace: None,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Set the namespace of the node
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// Add a key leaf to select a list entry
+    pub fn key(mut self, name: &str, value: &str) -> Self {
+        self.keys.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Build an `<action>` operation
+pub(crate) fn action_request(path: &[PathNode], action: &str, input: &str) -> io::Result<String> {
+    if path.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Action path must not be empty",
+        ));
+    }
+    // it would inherit the namespace of <action> otherwise
+    if path[0].namespace.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Top-level action path node {} has no namespace",
+                path[0].name
+            ),
+        ));
+    }
+    Ok(format!(
+        r#"<action xmlns="{}">{}</action>"#,
+        YANG_ACTION_NS,
+        action_xml(path, action, input)
+    ))
+}
+
+/// Build the content of the `<action>` element
+fn action_xml(path: &[PathNode], action: &str, input: &str) -> String {
+    let mut res = String::new();
+    for node in path {
+        res.push('<');
+        res.push_str(&node.name);
+        if let Some(ref ns) = node.namespace {
+            res.push_str(&format!(r#" xmlns="{}""#, escape(ns)));
+        }
+        res.push('>');
+        for (key, value) in &node.keys {
+            res.push_str(&format!("<{}>{}</{}>", key, escape(value), key));
+        }
+    }
+    res.push_str(&format!("<{}>{}</{}>", action, input, action));
+    for node in path.iter().rev() {
+        res.push_str(&format!("</{}>", node.name));
+    }
+    res
+}
+
+/// Parse the output of an action from its `<rpc-reply>`
+pub(crate) fn parse_action_output<T: DeserializeOwned>(resp: &str) -> io::Result<T> {
+    debug!("Got {}", resp);
+    from_str(resp.trim())
+}
+
+impl Connection {
+    /// Invoke a YANG 1.1 action and return the raw `<rpc-reply>`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path from the top-level node to the node the action is defined on
+    /// * `action` - Name of the action, in the namespace of the last path node
+    /// * `input` - XML of the action input parameters, may be empty
+    pub fn action_raw(
+        &mut self,
+        path: &[PathNode],
+        action: &str,
+        input: &str,
+    ) -> io::Result<String> {
+        self.rpc(&action_request(path, action, input)?)
+    }
+
+    /// Invoke a YANG 1.1 action and parse its output
+    ///
+    /// The output parameters are the children of `<rpc-reply>`, so `T` is
+    /// deserialized from the whole reply. Errors returned by the server are
+    /// reported as an [`crate::error::RpcError`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use netconf_rs::action::PathNode;
+    /// # use netconf_rs::Connection;
+    /// use serde_derive::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct ResetOutput {
+    ///     #[serde(rename = "reset-finished-at")]
+    ///     reset_finished_at: String,
+    /// }
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// let path = [PathNode::new("server")
+    ///     .namespace("urn:example:server-farm")
+    ///     .key("name", "apache-1")];
+    /// let output: ResetOutput = conn.action(
+    ///     &path,
+    ///     "reset",
+    ///     "<reset-at>2014-07-29T13:42:00Z</reset-at>",
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn action<T>(&mut self, path: &[PathNode], action: &str, input: &str) -> io::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        parse_action_output(&self.action_raw(path, action, input)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RpcError;
+    use crate::transport::mock::MockTransport;
+    use serde_derive::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct ResetOutput {
+        #[serde(rename = "reset-finished-at")]
+        reset_finished_at: String,
+    }
+
+    #[test]
+    fn invoke_action() {
+        let (transport, written) = MockTransport::new(&[
+            r#"
+<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <reset-finished-at xmlns="urn:example:server-farm">2014-07-29T13:42:12Z</reset-finished-at>
+</rpc-reply>"#,
+            r#"
+<rpc-reply message-id="102" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <rpc-error>
+        <error-type>application</error-type>
+        <error-tag>operation-failed</error-tag>
+        <error-severity>error</error-severity>
+        <error-message>Server is not running</error-message>
+    </rpc-error>
+</rpc-reply>"#,
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+        let path = [
+            PathNode::new("farm").namespace("urn:example:server-farm"),
+            PathNode::new("server").key("name", "apache-1"),
+        ];
+        let output: ResetOutput = conn
+            .action(&path, "reset", "<reset-at>2014-07-29T13:42:00Z</reset-at>")
+            .unwrap();
+        assert_eq!(output.reset_finished_at, "2014-07-29T13:42:12Z");
+        assert!(written.lock().unwrap()[1].contains(
+            r#"<action xmlns="urn:ietf:params:xml:ns:yang:1"><farm xmlns="urn:example:server-farm"><server><name>apache-1</name><reset><reset-at>2014-07-29T13:42:00Z</reset-at></reset></server></farm></action>"#
+        ));
+
+        let err = conn.action::<ResetOutput>(&path, "reset", "").unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RpcError>().unwrap();
+        assert_eq!(err.error_tag, "operation-failed");
+    }
+
+    #[test]
+    fn top_level_namespace() {
+        assert_eq!(
+            action_request(&[], "reset", "").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        let path = [PathNode::new("server").key("name", "apache-1")];
+        assert_eq!(
+            action_request(&path, "reset", "").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
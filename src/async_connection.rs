@@ -0,0 +1,330 @@
+//! Asynchronous NETCONF connection
+//!
+//! [`AsyncConnection`] offers the operations of [`crate::Connection`] as
+//! `async fn` on top of an [`AsyncTransport`]. It runs on the caller's Tokio
+//! runtime, so it can be used from async services without moving every call
+//! to a blocking thread pool.
+//!
+//! *Note: This module requires the `async` feature to be enabled.*
+
+use crate::action::{action_request, parse_action_output, PathNode};
+use crate::error::check_reply;
+use crate::framing::{is_recoverable, negotiate, Framer};
+use crate::monitoring::{
+    downloaded_schema, get_schema_request, netconf_state_request, parse_netconf_state,
+    parse_schema_list, reply_data, schema_downloads, schema_request, NetconfState, Schema,
+};
+use crate::partial_lock::{
+    parse_partial_lock_reply, partial_lock_request, partial_unlock_request, PartialLockReply,
+};
+use crate::pipeline::{reply_message_id, root_element};
+use crate::transport::AsyncTransport;
+use crate::yang_library::{
+    modules_state_library, yang_library_or_fallback, yang_library_request, YangLibrary,
+};
+use crate::{parse_hello, rpc_xml, GET_CONFIG, HELLO, MAX_HELLO_SIZE};
+use log::*;
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An asynchronous connection to a NETCONF server
+///
+/// This is the asynchronous counterpart of [`crate::Connection`]. It performs
+/// the hello exchange when created and provides the same NETCONF operations.
+///
+/// # Examples
+///
+/// ```ignore
+/// use netconf_rs::async_connection::AsyncConnection;
+/// use netconf_rs::transport::russh::{RusshConfig, RusshTransport};
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let transport = RusshTransport::connect_password_async(
+///     "192.168.1.1:830",
+///     "admin",
+///     "password",
+///     &RusshConfig::default(),
+/// )
+/// .await?;
+/// let mut conn = AsyncConnection::new(transport).await?;
+/// let config = conn.get_config().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// *Note: This example requires the `russh` feature to be enabled.*
+///
+/// The RPC futures are cancel safe: the reply to an RPC whose future was
+/// dropped, e.g. by `tokio::time::timeout`, is discarded when it arrives.
+pub struct AsyncConnection {
+    pub(crate) transport: Box<dyn AsyncTransport + Send + 'static>,
+    framer: Framer,
+    message_id: u64,
+    /// Message ids of the RPCs whose replies have not been read yet
+    outstanding: VecDeque<u64>,
+}
+
+impl AsyncConnection {
+    /// Creates a new NETCONF connection using the specified transport
+    ///
+    /// See [`crate::Connection::new`].
+    pub async fn new(transport: impl AsyncTransport + 'static) -> io::Result<AsyncConnection> {
+        let mut res = AsyncConnection {
+            transport: Box::from(transport),
+            framer: Framer::new(),
+            message_id: 100,
+            outstanding: VecDeque::new(),
+        };
+        res.hello().await?;
+        Ok(res)
+    }
+
+    /// Limit the size of incoming messages, or `None` for no limit
+    ///
+    /// See [`crate::Connection::set_max_message_size`].
+    pub fn set_max_message_size(&mut self, limit: Option<usize>) {
+        self.framer.set_max_message_size(limit);
+    }
+
+    /// Limit the size of incoming chunks, or `None` for no limit
+    ///
+    /// See [`crate::Connection::set_max_chunk_size`].
+    pub fn set_max_chunk_size(&mut self, limit: Option<usize>) {
+        self.framer.set_max_chunk_size(limit);
+    }
+
+    /// Performs the NETCONF hello handshake
+    async fn hello(&mut self) -> io::Result<()> {
+        debug!("Get capabilities of NetConf server");
+        self.framer
+            .write_message_async(&mut self.transport, HELLO)
+            .await?;
+        self.framer.set_max_message_size(Some(MAX_HELLO_SIZE));
+        let resp = self.framer.read_message_async(&mut self.transport).await;
+        self.framer.set_max_message_size(None);
+        let capabilities = parse_hello(&resp?)?;
+        self.framer.set_framing(negotiate(&capabilities));
+        Ok(())
+    }
+
+    /// Sends an RPC to the NETCONF server and waits for its reply
+    ///
+    /// See [`crate::Connection::rpc`].
+    pub async fn rpc(&mut self, operation: &str) -> io::Result<String> {
+        self.message_id += 1;
+        let message_id = self.message_id;
+        self.framer
+            .write_message_async(&mut self.transport, &rpc_xml(message_id, operation))
+            .await?;
+        self.outstanding.push_back(message_id);
+        let resp = self.read_reply(message_id).await?;
+        check_reply(&resp)?;
+        Ok(resp)
+    }
+
+    /// Read messages until the reply to `message_id` arrives
+    ///
+    /// Replies to RPCs whose futures were dropped and notifications are skipped.
+    async fn read_reply(&mut self, message_id: u64) -> io::Result<String> {
+        loop {
+            let resp = match self.framer.read_message_async(&mut self.transport).await {
+                Ok(resp) => resp,
+                // the rejected message is most likely the reply to the oldest request
+                Err(e) if is_recoverable(&e) => match self.outstanding.pop_front() {
+                    Some(id) if id == message_id => return Err(e),
+                    Some(id) => {
+                        debug!("Discarding rejected reply to message-id {}", id);
+                        continue;
+                    }
+                    None => return Err(e),
+                },
+                Err(e) => return Err(e),
+            };
+            if !matches!(root_element(&resp), Some(("rpc-reply", _))) {
+                warn!("Ignoring unexpected message: {}", resp);
+                continue;
+            }
+            // replies come in request order, so a reply without a
+            // message-id belongs to the oldest outstanding request
+            let id = match reply_message_id(&resp) {
+                Some(id) => id,
+                None => *self.outstanding.front().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "Unexpected rpc-reply")
+                })?,
+            };
+            match self
+                .outstanding
+                .iter()
+                .position(|outstanding| *outstanding == id)
+            {
+                Some(pos) => {
+                    self.outstanding.remove(pos);
+                }
+                None => {
+                    warn!("Ignoring reply with unknown message-id {}", id);
+                    continue;
+                }
+            }
+            if id == message_id {
+                return Ok(resp);
+            }
+            debug!("Discarding reply to cancelled RPC with message-id {}", id);
+        }
+    }
+
+    /// Retrieves the running configuration from the NETCONF server
+    ///
+    /// See [`crate::Connection::get_config`].
+    pub async fn get_config(&mut self) -> io::Result<String> {
+        self.rpc(GET_CONFIG).await
+    }
+
+    /// Lock the parts of the running datastore selected by XPath expressions
+    ///
+    /// Unlike [`crate::Connection::partial_lock`], the lock is not released
+    /// automatically; call [`AsyncConnection::partial_unlock`] with its lock id.
+    pub async fn partial_lock(
+        &mut self,
+        select: &[&str],
+        namespaces: &[(&str, &str)],
+    ) -> io::Result<PartialLockReply> {
+        let resp = self.rpc(&partial_lock_request(select, namespaces)).await?;
+        parse_partial_lock_reply(&resp)
+    }
+
+    /// Release a partial lock by its lock identifier
+    pub async fn partial_unlock(&mut self, lock_id: u32) -> io::Result<()> {
+        self.rpc(&partial_unlock_request(lock_id)).await?;
+        Ok(())
+    }
+
+    /// Retrieve a schema from the server with `<get-schema>`
+    ///
+    /// See [`crate::Connection::get_schema`].
+    pub async fn get_schema(
+        &mut self,
+        identifier: &str,
+        version: Option<&str>,
+        format: Option<&str>,
+    ) -> io::Result<String> {
+        let resp = self
+            .rpc(&get_schema_request(identifier, version, format))
+            .await?;
+        reply_data(&resp)
+    }
+
+    /// Retrieve the whole `/netconf-state` container
+    pub async fn get_netconf_state(&mut self) -> io::Result<NetconfState> {
+        let resp = self.rpc(&netconf_state_request("")).await?;
+        parse_netconf_state(&resp)
+    }
+
+    /// List the schemas in `/netconf-state/schemas`
+    pub async fn list_schemas(&mut self) -> io::Result<Vec<Schema>> {
+        let resp = self.rpc(&netconf_state_request("<schemas/>")).await?;
+        parse_schema_list(&resp)
+    }
+
+    /// Download every YANG and YIN schema listed by the server into `dir`
+    ///
+    /// See [`crate::Connection::download_schemas`].
+    pub async fn download_schemas(&mut self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        tokio::fs::create_dir_all(dir).await?;
+        let mut res = Vec::new();
+        for (schema, path) in schema_downloads(self.list_schemas().await?, dir) {
+            let resp = self.rpc(&schema_request(&schema)).await;
+            if let Some(text) = downloaded_schema(&schema, &path, resp)? {
+                tokio::fs::write(&path, text).await?;
+                res.push(path);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Retrieve the YANG library of the server
+    ///
+    /// See [`crate::Connection::get_yang_library`].
+    pub async fn get_yang_library(&mut self) -> io::Result<YangLibrary> {
+        let resp = self.rpc(&yang_library_request("yang-library")).await;
+        if let Some(library) = yang_library_or_fallback(resp)? {
+            return Ok(library);
+        }
+        modules_state_library(&self.rpc(&yang_library_request("modules-state")).await?)
+    }
+
+    /// Invoke a YANG 1.1 action and return the raw `<rpc-reply>`
+    ///
+    /// See [`crate::Connection::action_raw`].
+    pub async fn action_raw(
+        &mut self,
+        path: &[PathNode],
+        action: &str,
+        input: &str,
+    ) -> io::Result<String> {
+        self.rpc(&action_request(path, action, input)?).await
+    }
+
+    /// Invoke a YANG 1.1 action and parse its output
+    ///
+    /// See [`crate::Connection::action`].
+    pub async fn action<T>(&mut self, path: &[PathNode], action: &str, input: &str) -> io::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        parse_action_output(&self.action_raw(path, action, input).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::mock::MockTransport;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn async_operations() {
+        let (transport, written) = MockTransport::new(&[
+            r#"
+<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <data>
+        <top xmlns="http://www.h3c.com/netconf/config:1.0"/>
+    </data>
+</rpc-reply>"#,
+            r#"
+<rpc-reply message-id="102"
+    xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <lock-id xmlns="urn:ietf:params:xml:ns:netconf:partial-lock:1.0">3</lock-id>
+    <locked-node xmlns="urn:ietf:params:xml:ns:netconf:partial-lock:1.0">/top/VLAN</locked-node>
+</rpc-reply>"#,
+            r#"<rpc-reply message-id="103" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+        ]);
+        let mut conn = AsyncConnection::new(transport).await.unwrap();
+        let config = conn.get_config().await.unwrap();
+        assert!(config.contains("<top"));
+        let lock = conn.partial_lock(&["/top/VLAN"], &[]).await.unwrap();
+        assert_eq!(lock.lock_id, 3);
+        assert_eq!(lock.locked_node, vec!["/top/VLAN"]);
+        conn.partial_unlock(lock.lock_id).await.unwrap();
+
+        let written = written.lock().unwrap();
+        assert!(written[1].contains(r#"<rpc message-id="101""#));
+        assert!(written[3].contains(r#"<rpc message-id="103""#));
+        assert!(written[3].contains("<lock-id>3</lock-id>"));
+    }
+
+    #[tokio::test]
+    async fn cancelled_rpc() {
+        let (transport, _) = MockTransport::new(&[
+            "",
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>first</data></rpc-reply>"#,
+            r#"<notification xmlns="urn:ietf:params:xml:ns:netconf:notification:1.0"><eventTime>2024-01-01T00:00:00Z</eventTime></notification>"#,
+            r#"<rpc-reply message-id="102" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>second</data></rpc-reply>"#,
+        ]);
+        let mut conn = AsyncConnection::new(transport).await.unwrap();
+        let first = tokio::time::timeout(Duration::from_millis(10), conn.get_config()).await;
+        assert!(first.is_err());
+        assert!(conn.get_config().await.unwrap().contains("second"));
+    }
+}
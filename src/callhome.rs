@@ -0,0 +1,274 @@
+//! NETCONF Call Home
+//!
+//! With Call Home ([RFC 8071](https://datatracker.ietf.org/doc/html/rfc8071)),
+//! the device opens the TCP connection to the management system, e.g. because
+//! it sits behind NAT. Once connected, the roles are the usual ones: the
+//! management system is the SSH or TLS client and the device the server.
+//!
+//! Since the address of a device says little about its identity, calling
+//! devices are identified by their SSH host key or TLS certificate, which
+//! has to be on the allowlist of the [`CallHomeListener`].
+//!
+//! *Note: This module requires the `ssh2` or the `tls` feature to be enabled.*
+
+use crate::Connection;
+use log::*;
+use std::collections::HashSet;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+#[cfg(feature = "ssh2")]
+use crate::transport::auth::Auth;
+#[cfg(feature = "ssh2")]
+use crate::transport::host_key;
+#[cfg(feature = "ssh2")]
+use crate::transport::ssh::{SSHConfig, SSHTransport};
+#[cfg(feature = "tls")]
+use crate::transport::tls::{TlsConfig, TlsTransport};
+
+/// The IANA-assigned port for NETCONF Call Home over SSH
+pub const SSH_PORT: u16 = 4334;
+
+/// The IANA-assigned port for NETCONF Call Home over TLS
+pub const TLS_PORT: u16 = 4335;
+
+/// A device that called in
+pub struct CallHomeDevice {
+    /// The address the device connected from
+    pub addr: SocketAddr,
+    /// Fingerprint of the SSH host key or TLS certificate of the device
+    pub fingerprint: String,
+    /// The NETCONF session with the device
+    pub connection: Connection,
+}
+
+/// Accepts connections from devices calling home
+///
+/// # Examples
+///
+/// ```ignore
+/// use netconf_rs::callhome::{CallHomeListener, SSH_PORT};
+/// use netconf_rs::transport::auth::Auth;
+/// use netconf_rs::transport::ssh::SSHConfig;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let listener = CallHomeListener::bind(("0.0.0.0", SSH_PORT))?
+///     .allow("SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8");
+/// loop {
+///     match listener.accept_ssh("admin", &Auth::password("password"), &SSHConfig::default()) {
+///         Ok(mut device) => {
+///             println!("{} called in from {}", device.fingerprint, device.addr);
+///             let config = device.connection.get_config()?;
+///         }
+///         Err(e) => eprintln!("Rejected call home: {}", e),
+///     }
+/// }
+/// # }
+/// ```
+///
+/// *Note: This example requires the `ssh2` feature to be enabled.*
+pub struct CallHomeListener {
+    listener: TcpListener,
+    /// Fingerprints of the devices allowed to call in
+    allowed: HashSet<String>,
+    rpc_timeout: Option<Duration>,
+}
+
+impl CallHomeListener {
+    /// Listen for devices calling in on `addr`
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Local address to listen on, usually with port [`SSH_PORT`] or [`TLS_PORT`]
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<CallHomeListener> {
+        Ok(CallHomeListener {
+            listener: TcpListener::bind(addr)?,
+            allowed: HashSet::new(),
+            rpc_timeout: None,
+        })
+    }
+
+    /// Allow the device with this fingerprint to call in
+    ///
+    /// For SSH, this is the SHA-256 fingerprint of the host key as printed by
+    /// `ssh-keygen -lf`, e.g. `SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8`.
+    /// For TLS, this is the SHA-256 fingerprint of the certificate in hex,
+    /// see [`crate::transport::tls::TlsConfig::pin_fingerprint`].
+    pub fn allow(mut self, fingerprint: &str) -> Self {
+        self.allowed.insert(fingerprint.trim().to_string());
+        self
+    }
+
+    /// Set the default RPC timeout of the connections
+    pub fn rpc_timeout(mut self, timeout: Duration) -> Self {
+        self.rpc_timeout = Some(timeout);
+        self
+    }
+
+    /// The local address the listener is bound to
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Wait for the next device to call in
+    fn accept_tcp(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let (tcp, addr) = self.listener.accept()?;
+        debug!("Call home connection from {}", addr);
+        Ok((tcp, addr))
+    }
+
+    /// Wait for the next device to call in over SSH
+    ///
+    /// The host key of the device is checked against the allowlist before
+    /// the credentials are sent. A device that is not allowed is
+    /// disconnected with `io::ErrorKind::PermissionDenied`. A peer that does
+    /// not complete the SSH handshake within `SSHConfig::handshake_timeout`
+    /// fails with `io::ErrorKind::TimedOut`, so it can't keep other devices
+    /// from calling in.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_name` - SSH username
+    /// * `auth` - How to authenticate to the device
+    /// * `config` - SSH configuration options; the connection timeout, source
+    ///   address, jump hosts and host key policy do not apply
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the device and its NETCONF session, or an
+    /// `io::Error` if the device is not allowed or the session could not be
+    /// established
+    ///
+    /// *Note: This method requires the `ssh2` feature to be enabled.*
+    #[cfg(feature = "ssh2")]
+    pub fn accept_ssh(
+        &self,
+        user_name: &str,
+        auth: &Auth,
+        config: &SSHConfig,
+    ) -> io::Result<CallHomeDevice> {
+        let (tcp, addr) = self.accept_tcp()?;
+        let mut fingerprint = String::new();
+        let transport = SSHTransport::handshake(tcp, user_name, auth, config, |session| {
+            let hash = session
+                .host_key_hash(ssh2::HashType::Sha256)
+                .ok_or_else(|| io::Error::other("No host key"))?;
+            fingerprint = host_key::fingerprint(hash);
+            if self.allowed.contains(&fingerprint) {
+                Ok(())
+            } else {
+                Err(not_allowed(addr, &fingerprint))
+            }
+        })?;
+        Ok(CallHomeDevice {
+            addr,
+            fingerprint,
+            connection: Connection::with_rpc_timeout(transport, self.rpc_timeout)?,
+        })
+    }
+
+    /// Wait for the next device to call in over TLS
+    ///
+    /// The allowed fingerprints are pinned in addition to the verification
+    /// set up in `config`, so a device has to present an allowed certificate.
+    /// With an empty allowlist, every device passing the verification of
+    /// `config` is accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - TLS configuration options; the connection timeout does not apply
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the device and its NETCONF session, or an
+    /// `io::Error` if the device is not allowed or the session could not be
+    /// established
+    ///
+    /// *Note: This method requires the `tls` feature to be enabled.*
+    #[cfg(feature = "tls")]
+    pub fn accept_tls(&self, config: &TlsConfig) -> io::Result<CallHomeDevice> {
+        let (tcp, addr) = self.accept_tcp()?;
+        let config = self
+            .allowed
+            .iter()
+            .fold(config.clone(), |config, fingerprint| {
+                config.pin_fingerprint(fingerprint)
+            });
+        let transport = TlsTransport::from_stream(tcp, &config)?;
+        let fingerprint = transport.peer_fingerprint().unwrap_or_default();
+        Ok(CallHomeDevice {
+            addr,
+            fingerprint,
+            connection: Connection::with_rpc_timeout(transport, self.rpc_timeout)?,
+        })
+    }
+}
+
+#[cfg(feature = "ssh2")]
+fn not_allowed(addr: SocketAddr, fingerprint: &str) -> io::Error {
+    warn!(
+        "Rejecting call home from {} with unknown host key {}",
+        addr, fingerprint
+    );
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("Host key {} of {} is not allowed", fingerprint, addr),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[cfg(feature = "ssh2")]
+    #[test]
+    fn silent_ssh_device() {
+        use std::time::{Duration, Instant};
+
+        let listener = CallHomeListener::bind("127.0.0.1:0").unwrap();
+        // connects, but never sends an SSH banner
+        let device = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let start = Instant::now();
+        let config = SSHConfig::new().handshake_timeout(Duration::from_millis(200));
+        let err = listener
+            .accept_ssh("admin", &Auth::password("admin"), &config)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        drop(device);
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn tls_call_home() {
+        use crate::transport::mock::serve_one_rpc;
+        use crate::transport::tls::fingerprint;
+        use crate::transport::tls::tests::TestPki;
+        use rustls::{ServerConnection, StreamOwned};
+        use std::thread;
+
+        let pki = TestPki::new("callhome");
+        let listener = CallHomeListener::bind("127.0.0.1:0")
+            .unwrap()
+            .allow(&fingerprint(&pki.server_der));
+        let addr = listener.local_addr().unwrap();
+        let server = pki.server.clone();
+        let device = thread::spawn(move || {
+            let tcp = TcpStream::connect(addr).unwrap();
+            let mut stream = StreamOwned::new(ServerConnection::new(server).unwrap(), tcp);
+            serve_one_rpc(&mut stream).unwrap();
+        });
+
+        let mut device_session = listener.accept_tls(&pki.client_config()).unwrap();
+        assert_eq!(device_session.fingerprint, fingerprint(&pki.server_der));
+        assert!(device_session
+            .connection
+            .rpc("<commit/>")
+            .unwrap()
+            .contains("<ok/>"));
+        device.join().unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+//! Errors reported by NETCONF servers
+//!
+//! A server signals that an operation failed by returning one or more
+//! `<rpc-error>` elements in its `<rpc-reply>` (RFC 6241, section 4.3).
+//! They are surfaced as an `io::Error` of kind `Other` wrapping an [`RpcError`],
+//! which can be recovered with `io::Error::get_ref` and `downcast_ref`.
+
+use crate::xml::from_str;
+use serde_derive::Deserialize;
+use std::fmt;
+use std::io;
+
+/// A single `<rpc-error>` returned by the server
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct RpcError {
+    /// Conceptual layer of the error: `transport`, `rpc`, `protocol` or `application`
+    #[serde(rename = "error-type")]
+    pub error_type: String,
+    /// Error identifier, e.g. `lock-denied` or `invalid-value`
+    #[serde(rename = "error-tag")]
+    pub error_tag: String,
+    /// Either `error` or `warning`
+    #[serde(rename = "error-severity")]
+    pub error_severity: String,
+    /// Data-model or implementation specific error condition
+    #[serde(rename = "error-app-tag")]
+    pub error_app_tag: Option<String>,
+    /// XPath expression of the element associated with the error
+    #[serde(rename = "error-path")]
+    pub error_path: Option<String>,
+    /// Human readable description of the error
+    #[serde(rename = "error-message")]
+    pub error_message: Option<String>,
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} error: {}", self.error_type, self.error_tag)?;
+        if let Some(ref message) = self.error_message {
+            write!(f, ": {}", message.trim())?;
+        }
+        if let Some(ref path) = self.error_path {
+            write!(f, " (at {})", path.trim())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+#[derive(Debug, Deserialize)]
+struct ErrorReply {
+    #[serde(rename = "rpc-error", default)]
+    rpc_error: Vec<RpcError>,
+}
+
+/// Check an `<rpc-reply>` for `<rpc-error>` elements
+///
+/// Returns the first error whose severity is `error`. Warnings are ignored.
+pub fn check_reply(reply: &str) -> io::Result<()> {
+    if !reply.contains("rpc-error") {
+        return Ok(());
+    }
+    let reply: ErrorReply = from_str(reply.trim())?;
+    match reply
+        .rpc_error
+        .into_iter()
+        .find(|e| e.error_severity.trim() == "error")
+    {
+        Some(error) => Err(io::Error::other(error)),
+        None => Ok(()),
+    }
+}
+
+/// Whether an `io::Error` wraps an [`RpcError`] returned by the server
+pub(crate) fn is_rpc_error(error: &io::Error) -> bool {
+    error.get_ref().is_some_and(|e| e.is::<RpcError>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rpc_error() {
+        let resp = r#"
+<rpc-reply message-id="101"
+    xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <rpc-error>
+        <error-type>protocol</error-type>
+        <error-tag>lock-denied</error-tag>
+        <error-severity>error</error-severity>
+        <error-message>Lock failed, lock is already held</error-message>
+        <error-info>
+            <session-id>454</session-id>
+        </error-info>
+    </rpc-error>
+</rpc-reply>
+        "#;
+
+        let err = check_reply(resp).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RpcError>().unwrap();
+        assert_eq!(
+            err,
+            &RpcError {
+                error_type: String::from("protocol"),
+                error_tag: String::from("lock-denied"),
+                error_severity: String::from("error"),
+                error_app_tag: None,
+                error_path: None,
+                error_message: Some(String::from("Lock failed, lock is already held")),
+            }
+        );
+
+        assert!(check_reply(r#"<rpc-reply message-id="101"><ok/></rpc-reply>"#).is_ok());
+    }
+}
@@ -0,0 +1,331 @@
+//! Running operations across many devices
+//!
+//! [`Executor`] connects to a list of devices and runs the same operation
+//! on each of them, with a bounded number of devices in flight at once.
+//! Every device gets its own result: a device that cannot be reached, fails
+//! the operation, panics or exceeds its timeout does not affect the others.
+
+use crate::transport::Transport;
+use crate::Connection;
+use log::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Outcome of an operation on one device
+#[derive(Debug)]
+pub struct DeviceResult<K, R> {
+    /// The device
+    pub device: K,
+    /// What the operation returned, or why it failed
+    pub result: io::Result<R>,
+    /// Time taken to connect and run the operation
+    pub elapsed: Duration,
+}
+
+/// Runs an operation on many devices concurrently
+///
+/// Each device is handled on its own thread, with at most
+/// [`Executor::concurrency`] devices in flight.
+///
+/// # Examples
+///
+/// ```ignore
+/// use netconf_rs::executor::Executor;
+/// use netconf_rs::transport::ssh::SSHTransport;
+/// use netconf_rs::vendor::h3c::get_vlan_config;
+/// use std::time::Duration;
+///
+/// let switches: Vec<String> = (1..=300).map(|i| format!("10.0.0.{}:830", i)).collect();
+/// let results = Executor::new()
+///     .concurrency(32)
+///     .device_timeout(Duration::from_secs(120))
+///     .run(
+///         switches,
+///         |addr| SSHTransport::connect(addr, "admin", "password"),
+///         |_, conn| get_vlan_config(conn),
+///     );
+/// for res in results {
+///     if let Err(e) = res.result {
+///         eprintln!("{}: {}", res.device, e);
+///     }
+/// }
+/// ```
+///
+/// *Note: This example requires the `ssh2` feature to be enabled.*
+#[derive(Debug, Clone)]
+pub struct Executor {
+    concurrency: usize,
+    device_timeout: Option<Duration>,
+    rpc_timeout: Option<Duration>,
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self {
+            concurrency: 16,
+            device_timeout: None,
+            rpc_timeout: None,
+        }
+    }
+}
+
+impl Executor {
+    /// Create a new executor with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of devices handled at once
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Give up on a device that takes longer than `timeout`
+    ///
+    /// The device is reported with `io::ErrorKind::TimedOut` right away, but
+    /// its thread cannot be interrupted: it keeps its slot, and its session,
+    /// until the operation returns, so hanging devices never exceed the
+    /// concurrency. Unless [`Executor::rpc_timeout`] is set, every RPC is
+    /// bounded by `timeout` as well, so such threads end. Threads still
+    /// running when every device has a result are left to finish in the
+    /// background.
+    ///
+    /// Establishing the transport is only bounded by the timeouts of the
+    /// transport itself, e.g. `SSHConfig::connect_timeout`.
+    pub fn device_timeout(mut self, timeout: Duration) -> Self {
+        self.device_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the default RPC timeout of the connections
+    ///
+    /// Defaults to the device timeout, see [`Executor::device_timeout`].
+    pub fn rpc_timeout(mut self, timeout: Duration) -> Self {
+        self.rpc_timeout = Some(timeout);
+        self
+    }
+
+    /// Connect to every device and run `operation` on it
+    ///
+    /// # Arguments
+    ///
+    /// * `devices` - The devices to run the operation on
+    /// * `connect` - Creates a transport to a device
+    /// * `operation` - The operation to run on each connection
+    ///
+    /// # Returns
+    ///
+    /// One result per device, in the order of `devices`
+    pub fn run<K, T, C, O, R>(
+        &self,
+        devices: impl IntoIterator<Item = K>,
+        connect: C,
+        operation: O,
+    ) -> Vec<DeviceResult<K, R>>
+    where
+        K: Clone + Send + 'static,
+        T: Transport + 'static,
+        C: Fn(&K) -> io::Result<T> + Send + Sync + 'static,
+        O: Fn(&K, &mut Connection) -> io::Result<R> + Send + Sync + 'static,
+        R: Send + 'static,
+    {
+        let connect = Arc::new(connect);
+        let operation = Arc::new(operation);
+        let devices: Vec<K> = devices.into_iter().collect();
+        let mut results: Vec<Option<(io::Result<R>, Duration)>> =
+            devices.iter().map(|_| None).collect();
+        let mut queue: VecDeque<usize> = (0..devices.len()).collect();
+        // index of the device => start time
+        let mut in_flight: HashMap<usize, Instant> = HashMap::new();
+        // devices that timed out, but whose threads still hold their slots
+        let mut timed_out: HashSet<usize> = HashSet::new();
+        let (done, finished) = mpsc::channel();
+
+        while !queue.is_empty() || !in_flight.is_empty() {
+            while in_flight.len() + timed_out.len() < self.concurrency {
+                let Some(index) = queue.pop_front() else {
+                    break;
+                };
+                in_flight.insert(index, Instant::now());
+                let device = devices[index].clone();
+                let connect = connect.clone();
+                let operation = operation.clone();
+                let rpc_timeout = self.rpc_timeout.or(self.device_timeout);
+                let done = done.clone();
+                thread::spawn(move || {
+                    let result = catch_unwind(AssertUnwindSafe(|| {
+                        let mut conn =
+                            Connection::with_rpc_timeout(connect(&device)?, rpc_timeout)?;
+                        operation(&device, &mut conn)
+                    }))
+                    .unwrap_or_else(|_| Err(io::Error::other("Operation panicked")));
+                    // the receiver is gone if the device timed out and the run finished
+                    let _ = done.send((index, result));
+                });
+            }
+
+            let next_deadline = self
+                .device_timeout
+                .and_then(|timeout| in_flight.values().map(|start| *start + timeout).min());
+            let received = match next_deadline {
+                Some(deadline) => {
+                    finished.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => finished
+                    .recv()
+                    .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok((index, result)) => {
+                    // late results of timed out devices are dropped, their
+                    // slots are free now
+                    if let Some(start) = in_flight.remove(&index) {
+                        results[index] = Some((result, start.elapsed()));
+                    } else {
+                        timed_out.remove(&index);
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    let timeout = self.device_timeout.unwrap_or_default();
+                    in_flight.retain(|index, start| {
+                        if start.elapsed() < timeout {
+                            return true;
+                        }
+                        warn!("Device {} timed out after {:?}", index, timeout);
+                        results[*index] = Some((
+                            Err(io::Error::new(
+                                io::ErrorKind::TimedOut,
+                                format!("Device timed out after {:?}", timeout),
+                            )),
+                            start.elapsed(),
+                        ));
+                        timed_out.insert(*index);
+                        false
+                    });
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => unreachable!(),
+            }
+        }
+
+        devices
+            .into_iter()
+            .zip(results)
+            .map(|(device, result)| {
+                let (result, elapsed) = result.unwrap();
+                DeviceResult {
+                    device,
+                    result,
+                    elapsed,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::mock::MockTransport;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn run_on_devices() {
+        let results = Executor::new()
+            .concurrency(2)
+            .device_timeout(Duration::from_millis(200))
+            .run(
+                1..=5,
+                |device| match device {
+                    3 => Err(io::Error::new(io::ErrorKind::ConnectionRefused, "refused")),
+                    _ => Ok(MockTransport::new(&[
+                        r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data/></rpc-reply>"#,
+                    ])
+                    .0),
+                },
+                |device, conn| {
+                    match device {
+                        4 => thread::sleep(Duration::from_secs(2)),
+                        5 => panic!("bug"),
+                        _ => {}
+                    }
+                    conn.get_config().map(|_| *device * 10)
+                },
+            );
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].result.as_ref().unwrap(), &10);
+        assert_eq!(results[1].result.as_ref().unwrap(), &20);
+        assert_eq!(
+            results[2].result.as_ref().unwrap_err().kind(),
+            io::ErrorKind::ConnectionRefused
+        );
+        assert_eq!(
+            results[3].result.as_ref().unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        assert_eq!(results[4].device, 5);
+        assert!(results[4].result.is_err());
+    }
+
+    #[test]
+    fn timed_out_device_keeps_its_slot() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let (current, max) = (running.clone(), max_running.clone());
+        let results = Executor::new()
+            .concurrency(1)
+            .device_timeout(Duration::from_millis(100))
+            .run(
+                1..=3,
+                |_| Ok(MockTransport::new(&[]).0),
+                move |device, _| {
+                    let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                    max.fetch_max(now, Ordering::SeqCst);
+                    if *device == 1 {
+                        thread::sleep(Duration::from_millis(500));
+                    }
+                    current.fetch_sub(1, Ordering::SeqCst);
+                    Ok(*device)
+                },
+            );
+
+        assert_eq!(
+            results[0].result.as_ref().unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        assert_eq!(results[1].result.as_ref().unwrap(), &2);
+        assert_eq!(results[2].result.as_ref().unwrap(), &3);
+        assert_eq!(max_running.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn rpc_timeout_defaults_to_device_timeout() {
+        let results = Executor::new()
+            .concurrency(1)
+            .device_timeout(Duration::from_millis(100))
+            .run(
+                1..=2,
+                // device 1 never answers, without a read timeout the mock panics
+                |device| match device {
+                    1 => Ok(MockTransport::new(&[""]).0),
+                    _ => Ok(MockTransport::new(&[
+                        r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data/></rpc-reply>"#,
+                    ])
+                    .0),
+                },
+                |_, conn| conn.get_config(),
+            );
+
+        assert_eq!(
+            results[0].result.as_ref().unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        assert!(results[1].result.is_ok());
+    }
+}
@@ -0,0 +1,675 @@
+//! NETCONF message framing
+//!
+//! Transports only carry bytes; the [`Framer`] splits the incoming byte
+//! stream into NETCONF messages and frames outgoing ones, as specified in
+//! [RFC 6242](https://datatracker.ietf.org/doc/html/rfc6242#section-4):
+//!
+//! - **End-of-message framing** (`base:1.0`): every message is followed by
+//!   the `]]>]]>` delimiter.
+//! - **Chunked framing** (`base:1.1`): a message is sent as chunks prefixed
+//!   with their size, followed by an end-of-chunks marker.
+//!
+//! Sessions start with end-of-message framing for the hello exchange and
+//! switch to chunked framing when both peers advertise `base:1.1`.
+//!
+//! The framer itself performs no I/O: bytes are passed in with
+//! [`Framer::feed`], and messages are taken out with [`Framer::decode`].
+//! [`Framer::read_message`] and [`Framer::write_message`] combine this with
+//! a blocking reader or writer.
+
+use memmem::{Searcher, TwoWaySearcher};
+use std::fmt;
+use std::io;
+use std::io::{Read, Write};
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The capability announcing chunked framing
+pub const BASE_1_1: &str = "urn:ietf:params:netconf:base:1.1";
+
+const END_OF_MESSAGE: &[u8] = b"]]>]]>";
+
+/// Number of bytes requested from a transport per read
+const READ_SIZE: usize = 64 * 1024;
+
+/// The framing mechanism of a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Messages are terminated by `]]>]]>`
+    EndOfMessage,
+    /// Messages are sent as chunks prefixed with their size
+    Chunked,
+}
+
+/// Framing that applies after the hello exchange
+///
+/// # Arguments
+///
+/// * `capabilities` - The capabilities advertised by the server
+pub fn negotiate(capabilities: &[String]) -> Framing {
+    if capabilities.iter().any(|cap| cap == BASE_1_1) {
+        Framing::Chunked
+    } else {
+        Framing::EndOfMessage
+    }
+}
+
+/// A violation of the framing rules or limits by the peer
+///
+/// Reported as an `io::Error` of kind `InvalidData` wrapping the
+/// `FramingError`, which can be recovered with `io::Error::get_ref` and
+/// `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FramingError {
+    /// A message is larger than the maximum message size
+    MessageTooLarge {
+        /// The maximum message size
+        limit: usize,
+    },
+    /// A chunk is larger than the maximum chunk size
+    ChunkTooLarge {
+        /// The size of the chunk
+        size: usize,
+        /// The maximum chunk size
+        limit: usize,
+    },
+    /// A chunk header or end-of-chunks marker is malformed
+    MalformedChunk,
+    /// A message is not valid UTF-8
+    InvalidUtf8,
+}
+
+impl FramingError {
+    /// Whether the framer is still in sync with the byte stream
+    ///
+    /// After a recoverable error, the offending message is skipped and the
+    /// next one can be read. Otherwise the session has to be closed.
+    pub fn is_recoverable(&self) -> bool {
+        *self != FramingError::MalformedChunk
+    }
+}
+
+impl fmt::Display for FramingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramingError::MessageTooLarge { limit } => {
+                write!(f, "Message exceeds the limit of {} bytes", limit)
+            }
+            FramingError::ChunkTooLarge { size, limit } => write!(
+                f,
+                "Chunk of {} bytes exceeds the limit of {} bytes",
+                size, limit
+            ),
+            FramingError::MalformedChunk => write!(f, "Malformed chunk header"),
+            FramingError::InvalidUtf8 => write!(f, "Invalid UTF-8 in message"),
+        }
+    }
+}
+
+impl std::error::Error for FramingError {}
+
+/// The `FramingError` wrapped by an `io::Error`, if any
+pub fn framing_error(error: &io::Error) -> Option<&FramingError> {
+    error.get_ref()?.downcast_ref()
+}
+
+/// Whether an `io::Error` reports a recoverable [`FramingError`]
+pub(crate) fn is_recoverable(error: &io::Error) -> bool {
+    framing_error(error).is_some_and(FramingError::is_recoverable)
+}
+
+/// Splits a byte stream into NETCONF messages and frames outgoing messages
+///
+/// By default, messages and chunks of any size are accepted. Set limits with
+/// [`Framer::set_max_message_size`] and [`Framer::set_max_chunk_size`] when
+/// talking to untrusted peers, so they cannot exhaust the memory.
+///
+/// # Examples
+///
+/// ```
+/// use netconf_rs::framing::{Framer, Framing};
+///
+/// let mut framer = Framer::new();
+/// framer.set_framing(Framing::Chunked);
+/// framer.feed(b"\n#6\n<ok/>\n\n##\n");
+/// assert_eq!(framer.decode().unwrap().as_deref(), Some("<ok/>\n"));
+/// ```
+#[derive(Debug)]
+pub struct Framer {
+    framing: Framing,
+    /// Received bytes that have not been decoded yet
+    buffer: Vec<u8>,
+    /// Length of the prefix of `buffer` known not to contain `]]>]]>`
+    scanned: usize,
+    /// Bytes of the current chunk that have not been received yet
+    chunk_remaining: usize,
+    /// Data of the chunks of the current message decoded so far
+    message: Vec<u8>,
+    /// Size of the current message decoded so far
+    message_size: usize,
+    /// Whether the current message is discarded after an error
+    skipping: bool,
+    max_message_size: Option<usize>,
+    max_chunk_size: Option<usize>,
+}
+
+impl Default for Framer {
+    fn default() -> Self {
+        Self {
+            framing: Framing::EndOfMessage,
+            buffer: Vec::new(),
+            scanned: 0,
+            chunk_remaining: 0,
+            message: Vec::new(),
+            message_size: 0,
+            skipping: false,
+            max_message_size: None,
+            max_chunk_size: None,
+        }
+    }
+}
+
+impl Framer {
+    /// Create a framer using end-of-message framing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current framing mechanism
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Switch the framing mechanism, e.g. after the hello exchange
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
+    }
+
+    /// Limit the size of incoming messages, or `None` for no limit
+    ///
+    /// A larger message is rejected with [`FramingError::MessageTooLarge`]
+    /// as soon as the limit is exceeded, and the rest of it is discarded.
+    pub fn set_max_message_size(&mut self, limit: Option<usize>) {
+        self.max_message_size = limit;
+    }
+
+    /// Limit the size of incoming chunks, or `None` for no limit
+    ///
+    /// A message with a larger chunk is rejected with
+    /// [`FramingError::ChunkTooLarge`] and discarded.
+    pub fn set_max_chunk_size(&mut self, limit: Option<usize>) {
+        self.max_chunk_size = limit;
+    }
+
+    /// Append bytes received from the transport
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Take the next complete message out of the received bytes
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the message, `None` if more bytes are needed,
+    /// or an `io::Error` wrapping a [`FramingError`] if the bytes are not a
+    /// valid frame or exceed a limit
+    pub fn decode(&mut self) -> io::Result<Option<String>> {
+        let message = match self.framing {
+            Framing::EndOfMessage => self.decode_end_of_message()?,
+            Framing::Chunked => self.decode_chunked()?,
+        };
+        message
+            .map(|message| {
+                String::from_utf8(message).map_err(|_| framing(FramingError::InvalidUtf8))
+            })
+            .transpose()
+    }
+
+    /// Search the new bytes for `]]>]]>`
+    fn find_end_of_message(&mut self) -> Option<usize> {
+        // only search the new bytes, plus the tail of the old ones in case
+        // the delimiter was split between two reads
+        let from = self.scanned.saturating_sub(END_OF_MESSAGE.len() - 1);
+        let search = TwoWaySearcher::new(END_OF_MESSAGE);
+        let pos = search.search_in(&self.buffer[from..]).map(|pos| from + pos);
+        if pos.is_none() {
+            self.scanned = self.buffer.len();
+        }
+        pos
+    }
+
+    /// Discard bytes up to the end of the current message
+    ///
+    /// Returns `true` once the end of the message has been reached.
+    fn skip_end_of_message(&mut self) -> bool {
+        match self.find_end_of_message() {
+            Some(pos) => {
+                self.buffer.drain(..pos + END_OF_MESSAGE.len());
+                self.scanned = 0;
+                self.message_size = 0;
+                self.skipping = false;
+                true
+            }
+            None => {
+                // keep what could be the start of a delimiter
+                let keep = self.buffer.len().min(END_OF_MESSAGE.len() - 1);
+                self.buffer.drain(..self.buffer.len() - keep);
+                self.scanned = keep;
+                false
+            }
+        }
+    }
+
+    /// Start discarding the current message because it exceeds a limit
+    fn reject(&mut self, error: FramingError) -> io::Error {
+        self.skipping = true;
+        if self.framing == Framing::EndOfMessage {
+            self.skip_end_of_message();
+        }
+        framing(error)
+    }
+
+    fn exceeds_max_message_size(&self, size: usize) -> Option<FramingError> {
+        self.max_message_size
+            .filter(|limit| size > *limit)
+            .map(|limit| FramingError::MessageTooLarge { limit })
+    }
+
+    fn decode_end_of_message(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.skipping && !self.skip_end_of_message() {
+            return Ok(None);
+        }
+        let Some(pos) = self.find_end_of_message() else {
+            // the last bytes may be part of the delimiter
+            let size = self.buffer.len().saturating_sub(END_OF_MESSAGE.len() - 1);
+            return match self.exceeds_max_message_size(size) {
+                Some(error) => Err(self.reject(error)),
+                None => Ok(None),
+            };
+        };
+        // hand out the buffer itself as the message and keep only what follows
+        let rest = self.buffer.split_off(pos + END_OF_MESSAGE.len());
+        let mut message = std::mem::replace(&mut self.buffer, rest);
+        message.truncate(pos);
+        self.scanned = 0;
+        match self.exceeds_max_message_size(pos) {
+            Some(error) => Err(framing(error)),
+            None => Ok(Some(message)),
+        }
+    }
+
+    fn decode_chunked(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut message = std::mem::take(&mut self.message);
+        if self.decode_chunks(&mut message)? {
+            Ok(Some(message))
+        } else {
+            self.message = message;
+            Ok(None)
+        }
+    }
+
+    /// Move the data of the current message received so far into `out`
+    ///
+    /// This allows processing a message while it is still arriving: the
+    /// bytes are handed out as soon as they are known not to be part of the
+    /// framing. The limits apply as for [`Framer::decode`], but the message
+    /// is not checked for valid UTF-8.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `true` once the end of the message has been
+    /// reached, or an `io::Error` wrapping a [`FramingError`] if the bytes
+    /// are not a valid frame or exceed a limit
+    pub fn decode_partial(&mut self, out: &mut Vec<u8>) -> io::Result<bool> {
+        match self.framing {
+            Framing::EndOfMessage => {
+                if self.skipping && !self.skip_end_of_message() {
+                    return Ok(false);
+                }
+                if let Some(pos) = self.find_end_of_message() {
+                    let size = std::mem::take(&mut self.message_size) + pos;
+                    if let Some(error) = self.exceeds_max_message_size(size) {
+                        self.buffer.drain(..pos + END_OF_MESSAGE.len());
+                        self.scanned = 0;
+                        return Err(framing(error));
+                    }
+                    out.extend(self.buffer.drain(..pos));
+                    self.buffer.drain(..END_OF_MESSAGE.len());
+                    self.scanned = 0;
+                    return Ok(true);
+                }
+                // keep what could be the start of a delimiter
+                let keep = (1..END_OF_MESSAGE.len())
+                    .rev()
+                    .find(|len| self.buffer.ends_with(&END_OF_MESSAGE[..*len]))
+                    .unwrap_or(0);
+                let available = self.buffer.len() - keep;
+                self.message_size += available;
+                if let Some(error) = self.exceeds_max_message_size(self.message_size) {
+                    return Err(self.reject(error));
+                }
+                out.extend(self.buffer.drain(..available));
+                self.scanned = keep;
+                Ok(false)
+            }
+            Framing::Chunked => self.decode_chunks(out),
+        }
+    }
+
+    /// Move the data of the chunks received so far into `out`
+    ///
+    /// Returns `true` once the end-of-chunks marker has been consumed.
+    fn decode_chunks(&mut self, out: &mut Vec<u8>) -> io::Result<bool> {
+        let mut pos = 0;
+        let res = self.decode_chunks_from(&mut pos, out);
+        // drop the consumed bytes in one go
+        self.buffer.drain(..pos);
+        res
+    }
+
+    fn decode_chunks_from(&mut self, pos: &mut usize, out: &mut Vec<u8>) -> io::Result<bool> {
+        loop {
+            if self.chunk_remaining > 0 {
+                let available = (self.buffer.len() - *pos).min(self.chunk_remaining);
+                if !self.skipping {
+                    out.extend_from_slice(&self.buffer[*pos..*pos + available]);
+                }
+                *pos += available;
+                self.chunk_remaining -= available;
+                if self.chunk_remaining > 0 {
+                    return Ok(false);
+                }
+            }
+            let buffer = &self.buffer[*pos..];
+            // every chunk header and the end-of-chunks marker start with LF HASH
+            if buffer.len() < 3 {
+                return Ok(false);
+            }
+            if &buffer[..2] != b"\n#" {
+                return Err(malformed_chunk());
+            }
+            if buffer[2] == b'#' {
+                match buffer.get(3) {
+                    None => return Ok(false),
+                    Some(b'\n') => {
+                        *pos += 4;
+                        self.message_size = 0;
+                        // continue with the next message after a discarded one
+                        if std::mem::take(&mut self.skipping) {
+                            continue;
+                        }
+                        return Ok(true);
+                    }
+                    Some(_) => return Err(malformed_chunk()),
+                }
+            }
+            let Some(end) = buffer[2..].iter().position(|b| *b == b'\n') else {
+                // chunk-size has at most 10 digits
+                if buffer.len() > 12 {
+                    return Err(malformed_chunk());
+                }
+                return Ok(false);
+            };
+            let size = parse_chunk_size(&buffer[2..2 + end])?;
+            *pos += 2 + end + 1;
+            self.chunk_remaining = size;
+            if self.skipping {
+                continue;
+            }
+            if let Some(limit) = self.max_chunk_size.filter(|limit| size > *limit) {
+                return Err(self.reject(FramingError::ChunkTooLarge { size, limit }));
+            }
+            self.message_size += size;
+            if let Some(error) = self.exceeds_max_message_size(self.message_size) {
+                return Err(self.reject(error));
+            }
+        }
+    }
+
+    /// Frame a message for sending
+    pub fn encode(&self, message: &str) -> Vec<u8> {
+        let message = message.trim();
+        match self.framing {
+            Framing::EndOfMessage => [message.as_bytes(), END_OF_MESSAGE].concat(),
+            Framing::Chunked => format!("\n#{}\n{}\n##\n", message.len(), message).into_bytes(),
+        }
+    }
+
+    /// Read from `reader` until a complete message is available
+    ///
+    /// Bytes received before an error, e.g. a timeout, are kept, so the
+    /// message can still be read by a later call.
+    pub fn read_message<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<String> {
+        loop {
+            if let Some(message) = self.decode()? {
+                return Ok(message);
+            }
+            self.fill(reader)?;
+        }
+    }
+
+    /// Read the next bytes from `reader` into the buffer
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of bytes read, or an `io::Error` with
+    /// `io::ErrorKind::UnexpectedEof` if the reader is closed
+    pub fn fill<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<usize> {
+        // read straight into the buffer
+        let len = self.buffer.len();
+        self.buffer.resize(len + READ_SIZE, 0);
+        let res = reader.read(&mut self.buffer[len..]);
+        self.buffer.truncate(len + *res.as_ref().unwrap_or(&0));
+        match res? {
+            0 => Err(connection_closed()),
+            bytes => Ok(bytes),
+        }
+    }
+
+    /// Frame a message and write it to `writer`
+    pub fn write_message<W: Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        message: &str,
+    ) -> io::Result<()> {
+        writer.write_all(&self.encode(message))?;
+        writer.flush()
+    }
+
+    /// Read from `reader` until a complete message is available
+    ///
+    /// The future is cancellation safe: if it is dropped before completion,
+    /// no data is lost and a later call returns the same message.
+    ///
+    /// *Note: This method requires the `async` feature to be enabled.*
+    #[cfg(feature = "async")]
+    pub async fn read_message_async<R: AsyncRead + Unpin + ?Sized>(
+        &mut self,
+        reader: &mut R,
+    ) -> io::Result<String> {
+        loop {
+            if let Some(message) = self.decode()? {
+                return Ok(message);
+            }
+            // read straight into the spare capacity of the buffer, which
+            // is only extended once the read completes
+            self.buffer.reserve(READ_SIZE);
+            if reader.read_buf(&mut self.buffer).await? == 0 {
+                return Err(connection_closed());
+            }
+        }
+    }
+
+    /// Frame a message and write it to `writer`
+    ///
+    /// *Note: This method requires the `async` feature to be enabled.*
+    #[cfg(feature = "async")]
+    pub async fn write_message_async<W: AsyncWrite + Unpin + ?Sized>(
+        &self,
+        writer: &mut W,
+        message: &str,
+    ) -> io::Result<()> {
+        writer.write_all(&self.encode(message)).await?;
+        writer.flush().await
+    }
+}
+
+fn parse_chunk_size(digits: &[u8]) -> io::Result<usize> {
+    // chunk-size = [1-9] *DIGIT, at most 4294967295
+    if digits.is_empty() || digits[0] == b'0' || !digits.iter().all(u8::is_ascii_digit) {
+        return Err(malformed_chunk());
+    }
+    std::str::from_utf8(digits)
+        .ok()
+        .and_then(|digits| digits.parse::<u32>().ok())
+        .map(|size| size as usize)
+        .ok_or_else(malformed_chunk)
+}
+
+fn malformed_chunk() -> io::Error {
+    framing(FramingError::MalformedChunk)
+}
+
+fn framing(error: FramingError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn connection_closed() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "NETCONF channel closed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn end_of_message() {
+        let mut framer = Framer::new();
+        framer.feed(b"<hello/>]]>");
+        assert_eq!(framer.decode().unwrap(), None);
+        framer.feed(b"]]><rpc-reply/>]]>]]>");
+        assert_eq!(framer.decode().unwrap().as_deref(), Some("<hello/>"));
+        assert_eq!(framer.decode().unwrap().as_deref(), Some("<rpc-reply/>"));
+        assert_eq!(framer.decode().unwrap(), None);
+        assert_eq!(framer.encode("\n<rpc/>\n"), b"<rpc/>]]>]]>");
+    }
+
+    #[test]
+    fn chunked() {
+        let mut framer = Framer::new();
+        framer.set_framing(Framing::Chunked);
+        // example from RFC 6242 section 4.2
+        let mut stream: &[u8] = b"\n#4\n<rpc\n#18\n message-id=\"102\"\n\n#79\n     \
+xmlns=\"urn:ietf:params:xml:ns:netconf:base:1.0\">\n  <close-session/>\n</rpc>\n##\n";
+        assert_eq!(
+            framer.read_message(&mut stream).unwrap(),
+            "<rpc message-id=\"102\"\n     \
+xmlns=\"urn:ietf:params:xml:ns:netconf:base:1.0\">\n  <close-session/>\n</rpc>"
+        );
+        assert_eq!(
+            framer.read_message(&mut stream).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        assert_eq!(framer.encode("<ok/>"), b"\n#5\n<ok/>\n##\n");
+        for bad in [&b"\n#0\n"[..], b"\n#012\n", b"\n#x\n", b"<rpc/>"] {
+            let mut framer = Framer::new();
+            framer.set_framing(Framing::Chunked);
+            framer.feed(bad);
+            assert_eq!(
+                framer.decode().unwrap_err().kind(),
+                io::ErrorKind::InvalidData
+            );
+        }
+    }
+
+    #[test]
+    fn split_delimiter() {
+        let data = b"<rpc-reply><data>]]></data></rpc-reply>]]>]]><ok/>]]>]]>";
+        let mut framer = Framer::new();
+        let mut messages = Vec::new();
+        for byte in data {
+            framer.feed(&[*byte]);
+            while let Some(message) = framer.decode().unwrap() {
+                messages.push(message);
+            }
+        }
+        assert_eq!(
+            messages,
+            ["<rpc-reply><data>]]></data></rpc-reply>", "<ok/>"]
+        );
+
+        // a large message read in many pieces
+        let message = "<data/>".repeat(1 << 16);
+        let framed = [message.as_bytes(), END_OF_MESSAGE].concat();
+        let mut stream = &framed[..];
+        assert_eq!(framer.read_message(&mut stream).unwrap(), message);
+    }
+
+    #[test]
+    fn partial_messages() {
+        for (framing, data) in [
+            (
+                Framing::EndOfMessage,
+                &b"<data>]]><a/></data>]]>]]><ok/>]]>]]>"[..],
+            ),
+            (
+                Framing::Chunked,
+                b"\n#8\n<data>]]\n#12\n><a/></data>\n##\n\n#5\n<ok/>\n##\n",
+            ),
+        ] {
+            let mut framer = Framer::new();
+            framer.set_framing(framing);
+            let mut out = Vec::new();
+            let mut ends = 0;
+            for byte in data {
+                framer.feed(&[*byte]);
+                if framer.decode_partial(&mut out).unwrap() {
+                    ends += 1;
+                    if ends == 1 {
+                        assert_eq!(out, b"<data>]]><a/></data>");
+                        out.clear();
+                    }
+                }
+            }
+            assert_eq!(ends, 2);
+            assert_eq!(out, b"<ok/>");
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let mut framer = Framer::new();
+        framer.set_max_message_size(Some(8));
+        framer.feed(b"<data>");
+        assert_eq!(framer.decode().unwrap(), None);
+        framer.feed(b"<too-large/>");
+        let err = framer.decode().unwrap_err();
+        assert_eq!(
+            framing_error(&err),
+            Some(&FramingError::MessageTooLarge { limit: 8 })
+        );
+        // the rest of the message is skipped
+        framer.feed(b"</data>]]>]]><ok/>]]>]]>\xff]]>]]>");
+        assert_eq!(framer.decode().unwrap().as_deref(), Some("<ok/>"));
+        let err = framer.decode().unwrap_err();
+        assert_eq!(framing_error(&err), Some(&FramingError::InvalidUtf8));
+        assert!(framing_error(&err).unwrap().is_recoverable());
+
+        let mut framer = Framer::new();
+        framer.set_framing(Framing::Chunked);
+        framer.set_max_chunk_size(Some(4));
+        framer.feed(b"\n#5\n<data\n##\n\n#4\n<ok/\n#1\n>\n##\n");
+        let err = framer.decode().unwrap_err();
+        assert_eq!(
+            framing_error(&err),
+            Some(&FramingError::ChunkTooLarge { size: 5, limit: 4 })
+        );
+        assert_eq!(framer.decode().unwrap().as_deref(), Some("<ok/>"));
+
+        framer.feed(b"\n#1x\n");
+        let err = framer.decode().unwrap_err();
+        assert_eq!(framing_error(&err), Some(&FramingError::MalformedChunk));
+        assert!(!framing_error(&err).unwrap().is_recoverable());
+    }
+}
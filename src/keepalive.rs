@@ -0,0 +1,135 @@
+//! Session liveness
+//!
+//! Firewalls and NAT devices may silently drop idle sessions. SSH-level
+//! keepalives, configured on the transport (see `SSHConfig::keepalive_interval`
+//! and `RusshConfig::keepalive_interval`), keep such middleboxes from
+//! expiring the session. In addition, a cheap NETCONF probe checks that the
+//! server still answers RPCs.
+
+use crate::error::is_rpc_error;
+use crate::Connection;
+use log::*;
+use std::io;
+use std::time::Duration;
+
+/// Default time to wait for the reply to a probe
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// RPC sent to probe the session
+///
+/// Any reply proves the server is alive, including an `<rpc-error>` from a
+/// server without ietf-netconf-monitoring.
+const PROBE: &str = r#"
+    <get>
+        <filter type="subtree">
+            <netconf-state xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-monitoring">
+                <statistics><netconf-start-time/></statistics>
+            </netconf-state>
+        </filter>
+    </get>"#;
+
+impl Connection {
+    /// Check that the session is alive by sending a NETCONF probe
+    ///
+    /// The probe waits for at most the probe timeout, see
+    /// [`Connection::set_probe_timeout`], regardless of the default RPC
+    /// timeout, so a session silently dropped by a firewall is detected
+    /// instead of blocking. A session whose probe fails or times out is
+    /// considered lost.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # use netconf_rs::Connection;
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// if !conn.is_alive() {
+    ///     eprintln!("session lost");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn is_alive(&mut self) -> bool {
+        !self.failed && self.probe().is_ok()
+    }
+
+    /// Set how long a probe waits for its reply
+    ///
+    /// Defaults to [`DEFAULT_PROBE_TIMEOUT`].
+    pub fn set_probe_timeout(&mut self, timeout: Duration) {
+        self.probe_timeout = timeout;
+    }
+
+    /// Probe the session when idle for at least this interval
+    ///
+    /// See [`Connection::keepalive`].
+    pub fn set_keepalive_interval(&mut self, interval: Option<Duration>) {
+        self.keepalive_interval = interval;
+    }
+
+    /// Send a NETCONF probe if nothing was received for the keepalive interval
+    ///
+    /// Call this periodically, e.g. from the loop that drives the session.
+    /// Does nothing if no keepalive interval is set.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or the `io::Error` of the failed probe
+    pub fn keepalive(&mut self) -> io::Result<()> {
+        match self.keepalive_interval {
+            Some(interval) if self.last_activity.elapsed() >= interval => self.probe(),
+            _ => Ok(()),
+        }
+    }
+
+    fn probe(&mut self) -> io::Result<()> {
+        match self.rpc_with_timeout(PROBE, Some(self.probe_timeout)) {
+            Ok(_) => Ok(()),
+            Err(e) if is_rpc_error(&e) => Ok(()),
+            Err(e) => {
+                warn!("NETCONF keepalive probe failed: {}", e);
+                // a server that does not answer is as good as gone
+                self.failed = true;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::mock::MockTransport;
+
+    #[test]
+    fn probe_session() {
+        let (transport, written) = MockTransport::new(&[
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <rpc-error>
+        <error-type>protocol</error-type>
+        <error-tag>unknown-namespace</error-tag>
+        <error-severity>error</error-severity>
+    </rpc-error>
+</rpc-reply>"#,
+            "",
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+        conn.set_keepalive_interval(Some(Duration::from_secs(3600)));
+        conn.keepalive().unwrap();
+        assert_eq!(written.lock().unwrap().len(), 1);
+
+        assert!(conn.is_alive());
+        // no RPC timeout is set, the probe timeout keeps this from blocking
+        conn.set_keepalive_interval(Some(Duration::ZERO));
+        assert_eq!(
+            conn.keepalive().unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        assert!(!conn.is_alive());
+        assert_eq!(written.lock().unwrap().len(), 3);
+    }
+}
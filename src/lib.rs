@@ -11,7 +11,8 @@
 //! - **Multiple SSH backends**: Choose between `ssh2` (C-based) or `russh` (pure Rust) via Cargo features
 //! - **Flexible XML parsing**: Supports both `serde-xml-rs` and `quick-xml` backends
 //! - **Configurable security**: Fine-grained control over SSH algorithms and timeouts
-//! - **Async support**: The `russh` backend provides async/await support with Tokio
+//! - **Async support**: `AsyncConnection` provides all operations as `async fn` on the caller's
+//!   Tokio runtime, with the `russh` backend as its transport
 //!
 //! ## Quick Start
 //!
@@ -52,7 +53,8 @@
 //! ## Cargo Features
 //!
 //! - **`ssh2`** (default): SSH transport using the ssh2 library
-//! - **`russh`**: SSH transport using the russh library (pure Rust, async)
+//! - **`russh`**: SSH transport using the russh library (pure Rust, async), implies `async`
+//! - **`async`**: `AsyncTransport` trait, `AsyncConnection` and the shared `SessionHandle`
 //! - **`serde-xml`** (default): XML parsing with serde-xml-rs
 //! - **`quick-xml`**: XML parsing with quick-xml (faster alternative)
 //!
@@ -79,30 +81,98 @@
 //! - **`SSHTransport`** (in `transport::ssh` module): Synchronous SSH client using ssh2
 //! - **`RusshTransport`** (in `transport::russh` module): Asynchronous SSH client using russh
 //!
-//! Both support password and key-based authentication.
+//! Both support password, key-based and keyboard-interactive authentication through
+//! the shared `transport::auth::Auth`.
 
-use crate::transport::Transport;
+use crate::framing::{is_recoverable, negotiate, Framer, FramingError};
+use crate::transport::{read_with_timeout, Transport};
 use crate::xml::from_str;
 use log::*;
 use serde_derive::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
+use std::time::{Duration, Instant};
 
+pub mod action;
+#[cfg(feature = "async")]
+pub mod async_connection;
+#[cfg(any(feature = "ssh2", feature = "tls"))]
+pub mod callhome;
+pub mod error;
+pub mod executor;
+pub mod framing;
+pub mod keepalive;
+pub mod monitoring;
+pub mod partial_lock;
+pub mod pipeline;
+pub mod pool;
+pub mod reconnect;
+#[cfg(feature = "async")]
+pub mod session;
+pub mod stream;
 pub mod transport;
 pub mod vendor;
 pub mod xml;
+pub mod yang_library;
 
 #[derive(Debug, Deserialize)]
 struct Hello {
-    #[allow(dead_code)]
     pub capabilities: Capabilities,
 }
 
 #[derive(Debug, Deserialize)]
 struct Capabilities {
-    #[allow(dead_code)]
     pub capability: Vec<String>,
 }
 
+pub(crate) const HELLO: &str = r#"
+<?xml version="1.0" encoding="UTF-8"?>
+<hello xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <capabilities>
+        <capability>urn:ietf:params:netconf:base:1.0</capability>
+        <capability>urn:ietf:params:netconf:base:1.1</capability>
+    </capabilities>
+</hello>"#;
+
+/// Maximum size of the hello message of the server
+pub(crate) const MAX_HELLO_SIZE: usize = 1 << 20;
+
+pub(crate) const GET_CONFIG: &str = r#"
+    <get-config>
+        <source>
+            <running/>
+        </source>
+    </get-config>"#;
+
+/// Wrap an operation in an `<rpc>` element
+pub(crate) fn rpc_xml(message_id: u64, operation: &str) -> String {
+    format!(
+        r#"
+<?xml version="1.0" encoding="UTF-8"?>
+<rpc message-id="{}"
+    xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    {}
+</rpc>"#,
+        message_id, operation
+    )
+}
+
+/// Parse the hello message of the server
+///
+/// # Returns
+///
+/// A `Result` containing the capabilities of the server, or an `io::Error`
+pub(crate) fn parse_hello(resp: &str) -> io::Result<Vec<String>> {
+    let hello: Hello = from_str(resp.trim())?;
+    debug!("{:#?}", hello);
+    Ok(hello
+        .capabilities
+        .capability
+        .iter()
+        .map(|capability| String::from(capability.trim()))
+        .collect())
+}
+
 /// A connection to a NETCONF server
 ///
 /// This struct represents an active NETCONF session with a remote device.
@@ -128,6 +198,28 @@ struct Capabilities {
 /// *Note: This example requires the `ssh2` feature to be enabled.*
 pub struct Connection {
     pub(crate) transport: Box<dyn Transport + Send + 'static>,
+    framer: Framer,
+    /// Capabilities advertised by the server
+    capabilities: Vec<String>,
+    message_id: u64,
+    /// Message ids of the RPCs whose replies have not been read yet
+    outstanding: VecDeque<u64>,
+    /// Replies that have been read but not collected yet
+    replies: HashMap<u64, String>,
+    /// Outstanding RPCs that timed out, their replies are discarded
+    abandoned: HashSet<u64>,
+    /// RPCs whose replies were rejected by the framer
+    rejected: HashMap<u64, FramingError>,
+    /// Default timeout of an RPC
+    rpc_timeout: Option<Duration>,
+    /// Whether the transport has failed
+    failed: bool,
+    /// Probe the session when idle for this long
+    keepalive_interval: Option<Duration>,
+    /// Time to wait for the reply to a probe
+    probe_timeout: Duration,
+    /// When the last message was received
+    last_activity: Instant,
 }
 
 impl Connection {
@@ -160,13 +252,89 @@ impl Connection {
     ///
     /// *Note: This example requires the `ssh2` feature to be enabled.*
     pub fn new(transport: impl Transport + 'static) -> io::Result<Connection> {
+        Self::with_rpc_timeout(transport, None)
+    }
+
+    /// Creates a new NETCONF connection with a default RPC timeout
+    ///
+    /// The timeout applies to the hello exchange and to every RPC that does
+    /// not specify its own, see [`Connection::set_rpc_timeout`].
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - Any type implementing the `Transport` trait
+    /// * `timeout` - Maximum time to wait for a reply, or `None` to wait forever
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use netconf_rs::transport::ssh::SSHTransport;
+    /// use netconf_rs::Connection;
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let transport = SSHTransport::connect("192.168.1.1:830", "admin", "password")?;
+    /// let mut conn = Connection::with_rpc_timeout(transport, Some(Duration::from_secs(60)))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn with_rpc_timeout(
+        transport: impl Transport + 'static,
+        timeout: Option<Duration>,
+    ) -> io::Result<Connection> {
         let mut res = Connection {
             transport: Box::from(transport),
+            framer: Framer::new(),
+            capabilities: Vec::new(),
+            message_id: 100,
+            outstanding: VecDeque::new(),
+            replies: HashMap::new(),
+            abandoned: HashSet::new(),
+            rejected: HashMap::new(),
+            rpc_timeout: timeout,
+            failed: false,
+            keepalive_interval: None,
+            probe_timeout: keepalive::DEFAULT_PROBE_TIMEOUT,
+            last_activity: Instant::now(),
         };
         res.hello()?;
         Ok(res)
     }
 
+    /// The default timeout of an RPC
+    pub fn rpc_timeout(&self) -> Option<Duration> {
+        self.rpc_timeout
+    }
+
+    /// Set the default timeout of an RPC
+    ///
+    /// When no reply arrives in time, the RPC fails with
+    /// `io::ErrorKind::TimedOut`. The session stays usable: the late reply is
+    /// discarded when it arrives. The transport has to support
+    /// [`Transport::set_read_timeout`].
+    pub fn set_rpc_timeout(&mut self, timeout: Option<Duration>) {
+        self.rpc_timeout = timeout;
+    }
+
+    /// Limit the size of incoming messages, or `None` for no limit
+    ///
+    /// A larger reply fails its RPC with a [`FramingError`] and is
+    /// discarded, leaving the session usable. The hello message of the server
+    /// is always limited to 1 MiB.
+    pub fn set_max_message_size(&mut self, limit: Option<usize>) {
+        self.framer.set_max_message_size(limit);
+    }
+
+    /// Limit the size of incoming chunks with `base:1.1` framing, or `None`
+    /// for no limit
+    ///
+    /// See [`Connection::set_max_message_size`].
+    pub fn set_max_chunk_size(&mut self, limit: Option<usize>) {
+        self.framer.set_max_chunk_size(limit);
+    }
+
     /// Performs the NETCONF hello handshake
     ///
     /// This method sends a hello message to the server to exchange capabilities
@@ -174,28 +342,70 @@ impl Connection {
     /// message listing supported capabilities.
     ///
     /// This is called automatically during `Connection::new()` and typically not
-    /// called directly by users.
+    /// called directly by users. If both sides support `base:1.1`, chunked
+    /// framing is used from then on.
     fn hello(&mut self) -> io::Result<()> {
         debug!("Get capabilities of NetConf server");
-        self.transport.write_xml(
-            r#"
-<?xml version="1.0" encoding="UTF-8"?>
-<hello xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
-    <capabilities>
-        <capability>
-            urn:ietf:params:netconf:base:1.0
-        </capability>
-    </capabilities>
-</hello>
-]]>]]>
-        "#,
-        )?;
-        let resp = self.transport.read_xml()?;
-        let hello: Hello = from_str(resp.trim()).unwrap();
-        debug!("{:#?}", hello);
+        self.write_message(HELLO)?;
+        let deadline = self.rpc_timeout.map(|timeout| Instant::now() + timeout);
+        self.framer.set_max_message_size(Some(MAX_HELLO_SIZE));
+        let resp = self.read_message(deadline);
+        self.framer.set_max_message_size(None);
+        let resp = resp?;
+        self.capabilities = parse_hello(&resp)?;
+        self.framer.set_framing(negotiate(&self.capabilities));
         Ok(())
     }
 
+    /// The capabilities advertised by the server in its hello message
+    pub fn server_capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
+    /// Read the next message, giving up at `deadline`
+    pub(crate) fn read_message(&mut self, deadline: Option<Instant>) -> io::Result<String> {
+        let timeout = match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "Read timed out"));
+                }
+                Some(remaining)
+            }
+            None => None,
+        };
+        let framer = &mut self.framer;
+        let res = read_with_timeout(&mut *self.transport, timeout, |transport| {
+            framer.read_message(transport)
+        });
+        if res.is_ok() {
+            self.last_activity = Instant::now();
+        }
+        if let Err(e) = &res {
+            // a timed out read keeps the data received so far, and the
+            // framer skips a rejected message
+            if e.kind() != io::ErrorKind::TimedOut && !is_recoverable(e) {
+                self.failed = true;
+            }
+        }
+        res
+    }
+
+    /// Write a message to the transport
+    pub(crate) fn write_message(&mut self, data: &str) -> io::Result<()> {
+        let res = self.framer.write_message(&mut *self.transport, data);
+        // a partially written message leaves the session unusable
+        if res.is_err() {
+            self.failed = true;
+        }
+        res
+    }
+
+    /// Whether the transport has failed, leaving the session unusable
+    pub(crate) fn has_failed(&self) -> bool {
+        self.failed
+    }
+
     /// Retrieves the running configuration from the NETCONF server
     ///
     /// This method sends a `<get-config>` RPC to retrieve the running configuration
@@ -221,20 +431,52 @@ impl Connection {
     ///
     /// *Note: This example requires the `ssh2` feature to be enabled.*
     pub fn get_config(&mut self) -> io::Result<String> {
-        self.transport.write_xml(
-            r#"
-<?xml version="1.0" encoding="UTF-8"?>
-<rpc message-id="100"
-    xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
-    <get-config>
-        <source>
-            <running/>
-        </source>
-    </get-config>
-</rpc>
-        "#,
-        )?;
-        let resp = self.transport.read_xml()?;
-        Ok(resp)
+        self.rpc(GET_CONFIG)
+    }
+
+    /// Sends an RPC to the NETCONF server and waits for its reply
+    ///
+    /// The `operation` is wrapped in an `<rpc>` element carrying a fresh
+    /// `message-id`. If the server answers with an `<rpc-error>` of severity
+    /// `error`, it is returned as an `io::Error` wrapping an [`error::RpcError`].
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The XML of the operation element, e.g. `<get/>`
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the raw `<rpc-reply>` XML, or an `io::Error`
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # use netconf_rs::Connection;
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// let reply = conn.rpc("<lock><target><candidate/></target></lock>")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn rpc(&mut self, operation: &str) -> io::Result<String> {
+        let pending = self.send_rpc(operation)?;
+        self.wait_reply(pending)
+    }
+
+    /// Sends an RPC with its own timeout and waits for its reply
+    ///
+    /// Like [`Connection::rpc`], but `timeout` overrides the default set
+    /// with [`Connection::set_rpc_timeout`].
+    pub fn rpc_with_timeout(
+        &mut self,
+        operation: &str,
+        timeout: Option<Duration>,
+    ) -> io::Result<String> {
+        let pending = self.send_rpc(operation)?;
+        self.wait_reply_timeout(pending, timeout)
     }
 }
@@ -0,0 +1,696 @@
+//! NETCONF monitoring
+//!
+//! Operations of the `ietf-netconf-monitoring` YANG module
+//! ([RFC 6022](https://datatracker.ietf.org/doc/html/rfc6022)), which every
+//! NETCONF server is expected to implement regardless of vendor.
+
+use crate::error::is_rpc_error;
+use crate::xml::{escape, from_str, unescape};
+use crate::Connection;
+use log::*;
+use serde_derive::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Namespace of the `ietf-netconf-monitoring` YANG module
+pub const MONITORING_NS: &str = "urn:ietf:params:xml:ns:yang:ietf-netconf-monitoring";
+
+#[derive(Debug, Deserialize)]
+struct MonitoringReply {
+    data: MonitoringData,
+}
+
+#[derive(Debug, Deserialize)]
+struct MonitoringData {
+    #[serde(rename = "netconf-state")]
+    netconf_state: Option<NetconfState>,
+}
+
+/// State of the NETCONF server, i.e. the `/netconf-state` container
+///
+/// Containers the server did not return (e.g. because of a filter) are `None`.
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct NetconfState {
+    pub capabilities: Option<Capabilities>,
+    pub datastores: Option<Datastores>,
+    pub schemas: Option<Schemas>,
+    pub sessions: Option<Sessions>,
+    pub statistics: Option<Statistics>,
+}
+
+/// Capabilities announced by the server
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct Capabilities {
+    #[serde(default)]
+    pub capability: Vec<String>,
+}
+
+/// List of datastores on the server
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct Datastores {
+    #[serde(default)]
+    pub datastore: Vec<Datastore>,
+}
+
+/// A configuration datastore and the locks held on it
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct Datastore {
+    /// `running`, `candidate` or `startup`
+    pub name: String,
+    /// Present if the datastore is locked
+    pub locks: Option<Locks>,
+}
+
+impl Datastore {
+    /// Session identifiers holding a global or partial lock on the datastore
+    pub fn locked_by(&self) -> Vec<u32> {
+        match self.locks {
+            Some(ref locks) => locks
+                .global_lock
+                .iter()
+                .map(|lock| lock.locked_by_session)
+                .chain(locks.partial_lock.iter().map(|lock| lock.locked_by_session))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Locks held on a datastore
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct Locks {
+    /// Lock taken with `<lock>` on the whole datastore
+    #[serde(rename = "global-lock")]
+    pub global_lock: Option<GlobalLock>,
+    /// Locks taken with `<partial-lock>`
+    #[serde(rename = "partial-lock", default)]
+    pub partial_lock: Vec<PartialLockInfo>,
+}
+
+/// A lock on a whole datastore
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct GlobalLock {
+    #[serde(rename = "locked-by-session")]
+    pub locked_by_session: u32,
+    #[serde(rename = "locked-time")]
+    pub locked_time: String,
+}
+
+/// A partial lock on a datastore
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct PartialLockInfo {
+    #[serde(rename = "lock-id")]
+    pub lock_id: u32,
+    #[serde(rename = "locked-by-session")]
+    pub locked_by_session: u32,
+    #[serde(rename = "locked-time")]
+    pub locked_time: String,
+    /// XPath expressions of the `<partial-lock>` request
+    #[serde(default)]
+    pub select: Vec<String>,
+    /// Instance identifiers of the locked nodes
+    #[serde(rename = "locked-node", default)]
+    pub locked_node: Vec<String>,
+}
+
+/// List of sessions currently active on the server
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct Sessions {
+    #[serde(default)]
+    pub session: Vec<Session>,
+}
+
+/// A NETCONF session on the server
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct Session {
+    #[serde(rename = "session-id")]
+    pub session_id: u32,
+    /// Transport identity, e.g. `netconf-ssh`
+    pub transport: String,
+    pub username: String,
+    /// Host address of the client, if known
+    #[serde(rename = "source-host")]
+    pub source_host: Option<String>,
+    #[serde(rename = "login-time")]
+    pub login_time: String,
+    #[serde(rename = "in-rpcs", default)]
+    pub in_rpcs: u32,
+    #[serde(rename = "in-bad-rpcs", default)]
+    pub in_bad_rpcs: u32,
+    #[serde(rename = "out-rpc-errors", default)]
+    pub out_rpc_errors: u32,
+    #[serde(rename = "out-notifications", default)]
+    pub out_notifications: u32,
+}
+
+/// Server-wide counters
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct Statistics {
+    #[serde(rename = "netconf-start-time")]
+    pub netconf_start_time: Option<String>,
+    #[serde(rename = "in-bad-hellos", default)]
+    pub in_bad_hellos: u32,
+    #[serde(rename = "in-sessions", default)]
+    pub in_sessions: u32,
+    #[serde(rename = "dropped-sessions", default)]
+    pub dropped_sessions: u32,
+    #[serde(rename = "in-rpcs", default)]
+    pub in_rpcs: u32,
+    #[serde(rename = "in-bad-rpcs", default)]
+    pub in_bad_rpcs: u32,
+    #[serde(rename = "out-rpc-errors", default)]
+    pub out_rpc_errors: u32,
+    #[serde(rename = "out-notifications", default)]
+    pub out_notifications: u32,
+}
+
+/// List of schemas supported by the server
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct Schemas {
+    #[serde(default)]
+    pub schema: Vec<Schema>,
+}
+
+/// A data model schema supported by the server
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Schema {
+    /// Identifier of the schema, e.g. the YANG module name
+    pub identifier: String,
+    /// Version of the schema, e.g. the YANG revision date
+    #[serde(default)]
+    pub version: String,
+    /// Schema language, e.g. `yang` or `yin`
+    pub format: String,
+    /// XML namespace defined by the schema
+    #[serde(default)]
+    pub namespace: String,
+    /// Where the schema can be retrieved from: `NETCONF` or a URI
+    #[serde(default)]
+    pub location: Vec<String>,
+}
+
+impl Schema {
+    /// Schema format without the identity prefix, e.g. `yang` for `ncm:yang`
+    pub fn format_name(&self) -> &str {
+        let format = self.format.trim();
+        format.rsplit(':').next().unwrap_or(format)
+    }
+
+    /// Schema version, `None` if the server reported an empty version
+    pub fn version_name(&self) -> Option<&str> {
+        Some(self.version.trim()).filter(|v| !v.is_empty())
+    }
+
+    /// Whether the schema is a YANG module in `yang` or `yin` format
+    pub fn is_module(&self) -> bool {
+        matches!(self.format_name(), "yang" | "yin")
+    }
+
+    /// File name for the schema in the form `module@revision.yang`
+    ///
+    /// `None` if the identifier or version reported by the server is not a
+    /// plain name, e.g. `../../etc/passwd`, so it can't escape the directory
+    /// the schema is saved to.
+    pub fn file_name(&self) -> Option<String> {
+        let identifier = self.identifier.trim();
+        if !is_plain_name(identifier) || !self.version_name().map_or(true, is_plain_name) {
+            return None;
+        }
+        Some(match self.version_name() {
+            Some(version) => format!("{}@{}.{}", identifier, version, self.format_name()),
+            None => format!("{}.{}", identifier, self.format_name()),
+        })
+    }
+}
+
+/// Whether `name` can be used as a file name without leaving the directory
+fn is_plain_name(name: &str) -> bool {
+    // ':' for drive prefixes on Windows
+    !name.is_empty()
+        && !name.contains(['/', '\\', ':'])
+        && !name.contains("..")
+        && !Path::new(name).is_absolute()
+}
+
+/// Build a `<get-schema>` operation
+pub(crate) fn get_schema_request(
+    identifier: &str,
+    version: Option<&str>,
+    format: Option<&str>,
+) -> String {
+    let mut request = format!("<identifier>{}</identifier>", escape(identifier));
+    if let Some(version) = version {
+        request.push_str(&format!("<version>{}</version>", escape(version)));
+    }
+    if let Some(format) = format {
+        request.push_str(&format!("<format>{}</format>", escape(format)));
+    }
+    format!(
+        r#"<get-schema xmlns="{}">{}</get-schema>"#,
+        MONITORING_NS, request
+    )
+}
+
+/// Build a `<get>` operation for `/netconf-state` with `filter` as the content of the subtree filter
+pub(crate) fn netconf_state_request(filter: &str) -> String {
+    format!(
+        r#"
+    <get>
+        <filter type="subtree">
+            <netconf-state xmlns="{}">{}</netconf-state>
+        </filter>
+    </get>"#,
+        MONITORING_NS, filter
+    )
+}
+
+/// Build a `<get-schema>` operation for a listed schema
+pub(crate) fn schema_request(schema: &Schema) -> String {
+    get_schema_request(
+        schema.identifier.trim(),
+        schema.version_name(),
+        Some(schema.format_name()),
+    )
+}
+
+/// The YANG and YIN schemas to download into `dir`, with the path of each
+///
+/// Schemas whose name is not a plain file name are skipped with a warning.
+pub(crate) fn schema_downloads(schemas: Vec<Schema>, dir: &Path) -> Vec<(Schema, PathBuf)> {
+    schemas
+        .into_iter()
+        .filter(Schema::is_module)
+        .filter_map(|schema| match schema.file_name() {
+            Some(file_name) => Some((schema, dir.join(file_name))),
+            None => {
+                warn!("Skipping schema with unsafe name {}", schema.identifier);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Interpret the reply to the `<get-schema>` of a schema being saved to
+/// `path`, `None` if the server refused to return it
+pub(crate) fn downloaded_schema(
+    schema: &Schema,
+    path: &Path,
+    resp: io::Result<String>,
+) -> io::Result<Option<String>> {
+    match resp {
+        Ok(resp) => {
+            debug!("Saving schema {} to {}", schema.identifier, path.display());
+            reply_data(&resp).map(Some)
+        }
+        Err(e) if is_rpc_error(&e) => {
+            warn!("Failed to get schema {}: {}", schema.identifier, e);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Interpret the reply listing the schemas in `/netconf-state/schemas`
+pub(crate) fn parse_schema_list(resp: &str) -> io::Result<Vec<Schema>> {
+    Ok(parse_netconf_state(resp)?
+        .schemas
+        .map(|schemas| schemas.schema)
+        .unwrap_or_default())
+}
+
+pub(crate) fn parse_netconf_state(resp: &str) -> io::Result<NetconfState> {
+    let reply: MonitoringReply = from_str(resp.trim())?;
+    debug!("{:#?}", reply.data.netconf_state);
+    reply.data.netconf_state.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Missing <netconf-state> in reply",
+        )
+    })
+}
+
+/// Extract the content of the `<data>` element of an `<rpc-reply>`
+///
+/// Text content (e.g. YANG) is unescaped, element content (e.g. YIN) is returned verbatim.
+pub(crate) fn reply_data(reply: &str) -> io::Result<String> {
+    let missing = || io::Error::new(io::ErrorKind::InvalidData, "Missing <data> in reply");
+    let mut pos = 0;
+    while let Some(offset) = reply[pos..].find('<') {
+        let start = pos + offset + 1;
+        let name_end = reply[start..]
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .map(|n| start + n)
+            .ok_or_else(missing)?;
+        let name = &reply[start..name_end];
+        pos = name_end;
+        if name.rsplit(':').next() != Some("data") {
+            continue;
+        }
+        let tag_end = name_end + reply[name_end..].find('>').ok_or_else(missing)?;
+        if reply[..tag_end].ends_with('/') {
+            return Ok(String::new());
+        }
+        let close = format!("</{}", name);
+        let content_end = reply.rfind(&close).ok_or_else(missing)?;
+        let content = &reply[tag_end + 1..content_end];
+        let trimmed = content.trim_start();
+        return if trimmed.starts_with('<') && !trimmed.starts_with("<![CDATA[") {
+            Ok(String::from(content.trim()))
+        } else {
+            unescape(content)
+        };
+    }
+    Err(missing())
+}
+
+impl Connection {
+    /// Retrieve a schema from the server with `<get-schema>`
+    ///
+    /// # Arguments
+    ///
+    /// * `identifier` - Schema identifier, e.g. the YANG module name
+    /// * `version` - Optional schema version, e.g. the YANG revision date
+    /// * `format` - Optional schema format, e.g. `yang` or `yin`
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the schema text, or an `io::Error`
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # use netconf_rs::Connection;
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// let yang = conn.get_schema("ietf-interfaces", Some("2018-02-20"), Some("yang"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn get_schema(
+        &mut self,
+        identifier: &str,
+        version: Option<&str>,
+        format: Option<&str>,
+    ) -> io::Result<String> {
+        let resp = self.rpc(&get_schema_request(identifier, version, format))?;
+        reply_data(&resp)
+    }
+
+    /// Retrieve the whole `/netconf-state` container
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # use netconf_rs::Connection;
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// let state = conn.get_netconf_state()?;
+    /// for session in state.sessions.map(|s| s.session).unwrap_or_default() {
+    ///     println!("{} from {:?}", session.username, session.source_host);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn get_netconf_state(&mut self) -> io::Result<NetconfState> {
+        parse_netconf_state(&self.rpc(&netconf_state_request(""))?)
+    }
+
+    /// List the schemas in `/netconf-state/schemas`
+    pub fn list_schemas(&mut self) -> io::Result<Vec<Schema>> {
+        parse_schema_list(&self.rpc(&netconf_state_request("<schemas/>"))?)
+    }
+
+    /// Download every YANG and YIN schema listed by the server into `dir`
+    ///
+    /// Files are named `module@revision.yang` (or `.yin`). Schemas the server
+    /// refuses to return, or whose name is not a plain file name, are skipped
+    /// with a warning.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the paths of the written files, or an `io::Error`
+    pub fn download_schemas(&mut self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        let mut res = Vec::new();
+        for (schema, path) in schema_downloads(self.list_schemas()?, dir) {
+            let resp = self.rpc(&schema_request(&schema));
+            if let Some(text) = downloaded_schema(&schema, &path, resp)? {
+                fs::write(&path, text)?;
+                res.push(path);
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::mock::MockTransport;
+    use crate::Connection;
+    use std::fs;
+
+    #[test]
+    fn parse_netconf_state() {
+        let (transport, _) = MockTransport::new(&[r#"
+<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <data>
+        <netconf-state xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-monitoring">
+            <capabilities>
+                <capability>urn:ietf:params:netconf:base:1.1</capability>
+            </capabilities>
+            <datastores>
+                <datastore>
+                    <name>running</name>
+                    <locks>
+                        <partial-lock>
+                            <lock-id>1</lock-id>
+                            <locked-by-session>7</locked-by-session>
+                            <locked-time>2024-01-01T00:00:00Z</locked-time>
+                            <select>/top/VLAN</select>
+                            <locked-node>/top/VLAN</locked-node>
+                        </partial-lock>
+                    </locks>
+                </datastore>
+                <datastore>
+                    <name>candidate</name>
+                    <locks>
+                        <global-lock>
+                            <locked-by-session>8</locked-by-session>
+                            <locked-time>2024-01-01T00:00:01Z</locked-time>
+                        </global-lock>
+                    </locks>
+                </datastore>
+                <datastore>
+                    <name>startup</name>
+                </datastore>
+            </datastores>
+            <sessions>
+                <session>
+                    <session-id>7</session-id>
+                    <transport xmlns:ncm="urn:ietf:params:xml:ns:yang:ietf-netconf-monitoring">ncm:netconf-ssh</transport>
+                    <username>admin</username>
+                    <source-host>192.0.2.10</source-host>
+                    <login-time>2024-01-01T00:00:00Z</login-time>
+                    <in-rpcs>12</in-rpcs>
+                    <in-bad-rpcs>1</in-bad-rpcs>
+                    <out-rpc-errors>1</out-rpc-errors>
+                    <out-notifications>0</out-notifications>
+                </session>
+            </sessions>
+            <statistics>
+                <netconf-start-time>2023-12-31T00:00:00Z</netconf-start-time>
+                <in-bad-hellos>0</in-bad-hellos>
+                <in-sessions>42</in-sessions>
+                <dropped-sessions>3</dropped-sessions>
+                <in-rpcs>1000</in-rpcs>
+                <in-bad-rpcs>2</in-bad-rpcs>
+                <out-rpc-errors>5</out-rpc-errors>
+                <out-notifications>0</out-notifications>
+            </statistics>
+        </netconf-state>
+    </data>
+</rpc-reply>"#]);
+        let mut conn = Connection::new(transport).unwrap();
+        let state = conn.get_netconf_state().unwrap();
+
+        let datastores = state.datastores.unwrap().datastore;
+        assert_eq!(datastores.len(), 3);
+        assert_eq!(datastores[0].locked_by(), vec![7]);
+        assert_eq!(
+            datastores[0].locks.as_ref().unwrap().partial_lock[0].select,
+            vec![String::from("/top/VLAN")]
+        );
+        assert_eq!(datastores[1].locked_by(), vec![8]);
+        assert_eq!(datastores[2].locked_by(), Vec::<u32>::new());
+
+        assert_eq!(
+            state.sessions.unwrap().session,
+            vec![Session {
+                session_id: 7,
+                transport: String::from("ncm:netconf-ssh"),
+                username: String::from("admin"),
+                source_host: Some(String::from("192.0.2.10")),
+                login_time: String::from("2024-01-01T00:00:00Z"),
+                in_rpcs: 12,
+                in_bad_rpcs: 1,
+                out_rpc_errors: 1,
+                out_notifications: 0,
+            }]
+        );
+        let statistics = state.statistics.unwrap();
+        assert_eq!(statistics.in_sessions, 42);
+        assert_eq!(statistics.dropped_sessions, 3);
+        assert!(state.schemas.is_none());
+    }
+
+    #[test]
+    fn download_schemas() {
+        let (transport, written) = MockTransport::new(&[
+            r#"
+<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <data>
+        <netconf-state xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-monitoring">
+            <schemas>
+                <schema>
+                    <identifier>example-a</identifier>
+                    <version>2024-01-01</version>
+                    <format>yang</format>
+                    <namespace>urn:example:a</namespace>
+                    <location>NETCONF</location>
+                </schema>
+                <schema>
+                    <identifier>example-b</identifier>
+                    <version></version>
+                    <format>ncm:yin</format>
+                    <namespace>urn:example:b</namespace>
+                    <location>NETCONF</location>
+                </schema>
+                <schema>
+                    <identifier>example-c</identifier>
+                    <version>2024-01-01</version>
+                    <format>xsd</format>
+                    <namespace>urn:example:c</namespace>
+                    <location>NETCONF</location>
+                </schema>
+            </schemas>
+        </netconf-state>
+    </data>
+</rpc-reply>"#,
+            r#"
+<rpc-reply message-id="102" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <data xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-monitoring">module example-a {
+  namespace "urn:example:a";
+  description "a &lt; b &amp;&amp; c";
+}</data>
+</rpc-reply>"#,
+            r#"
+<rpc-reply message-id="103" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <ncm:data xmlns:ncm="urn:ietf:params:xml:ns:yang:ietf-netconf-monitoring">
+        <module name="example-b" xmlns="urn:ietf:params:xml:ns:yang:yin:1"/>
+    </ncm:data>
+</rpc-reply>"#,
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+        let dir = std::env::temp_dir().join(format!("netconf-rs-schemas-{}", std::process::id()));
+        let paths = conn.download_schemas(&dir).unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                dir.join("example-a@2024-01-01.yang"),
+                dir.join("example-b.yin")
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(&paths[0]).unwrap(),
+            "module example-a {\n  namespace \"urn:example:a\";\n  description \"a < b && c\";\n}"
+        );
+        assert_eq!(
+            fs::read_to_string(&paths[1]).unwrap(),
+            r#"<module name="example-b" xmlns="urn:ietf:params:xml:ns:yang:yin:1"/>"#
+        );
+        assert!(written.lock().unwrap()[2]
+            .contains("<identifier>example-a</identifier><version>2024-01-01</version>"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn download_schemas_unsafe_names() {
+        let (transport, written) = MockTransport::new(&[r#"
+<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <data>
+        <netconf-state xmlns="urn:ietf:params:xml:ns:yang:ietf-netconf-monitoring">
+            <schemas>
+                <schema>
+                    <identifier>../../etc/x</identifier>
+                    <version></version>
+                    <format>yang</format>
+                    <namespace>urn:example:x</namespace>
+                    <location>NETCONF</location>
+                </schema>
+                <schema>
+                    <identifier>/tmp/x</identifier>
+                    <version>2024-01-01</version>
+                    <format>yang</format>
+                    <namespace>urn:example:x</namespace>
+                    <location>NETCONF</location>
+                </schema>
+                <schema>
+                    <identifier>example-x</identifier>
+                    <version>..</version>
+                    <format>yang</format>
+                    <namespace>urn:example:x</namespace>
+                    <location>NETCONF</location>
+                </schema>
+            </schemas>
+        </netconf-state>
+    </data>
+</rpc-reply>"#]);
+        let mut conn = Connection::new(transport).unwrap();
+        let dir =
+            std::env::temp_dir().join(format!("netconf-rs-unsafe-schemas-{}", std::process::id()));
+        assert!(conn.download_schemas(&dir).unwrap().is_empty());
+        // only the hello and <get> were sent, no <get-schema>
+        assert_eq!(written.lock().unwrap().len(), 2);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn schema_file_names() {
+        let schema = |identifier: &str, version: &str| Schema {
+            identifier: identifier.to_string(),
+            version: version.to_string(),
+            format: "yang".to_string(),
+            namespace: String::new(),
+            location: vec![],
+        };
+        assert_eq!(
+            schema("example-a", "2024-01-01").file_name().unwrap(),
+            "example-a@2024-01-01.yang"
+        );
+        assert_eq!(
+            schema(" example-a ", "").file_name().unwrap(),
+            "example-a.yang"
+        );
+        for (identifier, version) in [
+            ("", ""),
+            ("..", ""),
+            ("a/b", ""),
+            ("a\\b", ""),
+            ("C:x", ""),
+            ("example-a", "../x"),
+        ] {
+            assert!(schema(identifier, version).file_name().is_none());
+        }
+    }
+}
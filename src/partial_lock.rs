@@ -0,0 +1,219 @@
+//! Partial locking of configuration subtrees
+//!
+//! Implements the `<partial-lock>` and `<partial-unlock>` operations of
+//! [RFC 5717](https://datatracker.ietf.org/doc/html/rfc5717). Unlike `<lock>`,
+//! a partial lock only covers the nodes of the running datastore selected by
+//! a set of XPath expressions, so several sessions can edit disjoint parts of
+//! the same device at once.
+
+use crate::xml::{escape, from_str};
+use crate::Connection;
+use log::*;
+use serde_derive::Deserialize;
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+/// Namespace of the partial lock operations
+pub const PARTIAL_LOCK_NS: &str = "urn:ietf:params:xml:ns:netconf:partial-lock:1.0";
+
+/// Reply to a `<partial-lock>` request
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct PartialLockReply {
+    /// Lock identifier assigned by the server
+    #[serde(rename = "lock-id")]
+    pub lock_id: u32,
+    /// Instance identifiers of the nodes covered by the lock
+    #[serde(rename = "locked-node", default)]
+    pub locked_node: Vec<String>,
+}
+
+/// Build a `<partial-lock>` operation
+pub(crate) fn partial_lock_request(select: &[&str], namespaces: &[(&str, &str)]) -> String {
+    let xmlns = namespaces
+        .iter()
+        .map(|(prefix, ns)| format!(r#" xmlns:{}="{}""#, prefix, escape(ns)))
+        .collect::<String>();
+    let select = select
+        .iter()
+        .map(|xpath| format!("<select{}>{}</select>", xmlns, escape(xpath)))
+        .collect::<String>();
+    format!(
+        r#"<partial-lock xmlns="{}">{}</partial-lock>"#,
+        PARTIAL_LOCK_NS, select
+    )
+}
+
+/// Build a `<partial-unlock>` operation
+pub(crate) fn partial_unlock_request(lock_id: u32) -> String {
+    format!(
+        r#"<partial-unlock xmlns="{}"><lock-id>{}</lock-id></partial-unlock>"#,
+        PARTIAL_LOCK_NS, lock_id
+    )
+}
+
+pub(crate) fn parse_partial_lock_reply(resp: &str) -> io::Result<PartialLockReply> {
+    let mut reply: PartialLockReply = from_str(resp.trim())?;
+    for node in reply.locked_node.iter_mut() {
+        *node = String::from(node.trim());
+    }
+    debug!("{:#?}", reply);
+    Ok(reply)
+}
+
+/// A partial lock held on the running datastore
+///
+/// The lock is released with `<partial-unlock>` when the guard is dropped.
+/// Use [`PartialLock::unlock`] instead to find out whether the release succeeded.
+///
+/// The guard dereferences to the underlying [`Connection`], so the locked
+/// nodes can be edited while the lock is held.
+pub struct PartialLock<'a> {
+    conn: &'a mut Connection,
+    lock_id: u32,
+    locked_nodes: Vec<String>,
+    released: bool,
+}
+
+impl PartialLock<'_> {
+    /// Lock identifier assigned by the server
+    pub fn lock_id(&self) -> u32 {
+        self.lock_id
+    }
+
+    /// Instance identifiers of the nodes covered by the lock
+    pub fn locked_nodes(&self) -> &[String] {
+        &self.locked_nodes
+    }
+
+    /// Release the lock
+    pub fn unlock(mut self) -> io::Result<()> {
+        self.released = true;
+        self.conn.partial_unlock(self.lock_id)
+    }
+}
+
+impl Deref for PartialLock<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+impl DerefMut for PartialLock<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn
+    }
+}
+
+impl Drop for PartialLock<'_> {
+    fn drop(&mut self) {
+        if !self.released {
+            if let Err(e) = self.conn.partial_unlock(self.lock_id) {
+                warn!("Failed to release partial lock {}: {}", self.lock_id, e);
+            }
+        }
+    }
+}
+
+impl Connection {
+    /// Lock the parts of the running datastore selected by XPath expressions
+    ///
+    /// # Arguments
+    ///
+    /// * `select` - XPath 1.0 expressions selecting the nodes to lock
+    /// * `namespaces` - `(prefix, namespace)` pairs for the prefixes used in `select`
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a [`PartialLock`] guard, or an `io::Error`.
+    /// A lock conflict is reported as an [`crate::error::RpcError`] with tag `lock-denied`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # use netconf_rs::Connection;
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// let mut lock = conn.partial_lock(
+    ///     &["/h3c:top/h3c:VLAN"],
+    ///     &[("h3c", "http://www.h3c.com/netconf/config:1.0")],
+    /// )?;
+    /// println!("lock {} holds {:?}", lock.lock_id(), lock.locked_nodes());
+    /// lock.get_config()?;
+    /// lock.unlock()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn partial_lock(
+        &mut self,
+        select: &[&str],
+        namespaces: &[(&str, &str)],
+    ) -> io::Result<PartialLock<'_>> {
+        let resp = self.rpc(&partial_lock_request(select, namespaces))?;
+        let reply = parse_partial_lock_reply(&resp)?;
+        Ok(PartialLock {
+            conn: self,
+            lock_id: reply.lock_id,
+            locked_nodes: reply.locked_node,
+            released: false,
+        })
+    }
+
+    /// Release a partial lock by its lock identifier
+    pub fn partial_unlock(&mut self, lock_id: u32) -> io::Result<()> {
+        self.rpc(&partial_unlock_request(lock_id))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::mock::MockTransport;
+    use crate::Connection;
+
+    #[test]
+    fn partial_lock_and_release() {
+        let (transport, written) = MockTransport::new(&[
+            r#"
+<nc:rpc-reply nc:message-id="101"
+    xmlns:nc="urn:ietf:params:xml:ns:netconf:base:1.0"
+    xmlns="urn:ietf:params:xml:ns:netconf:partial-lock:1.0">
+    <lock-id>127</lock-id>
+    <locked-node xmlns:rte="http://example.com/ns/route">
+        /rte:routing/rte:ipv4/rte:route[rte:address='192.0.2.1']
+    </locked-node>
+    <locked-node xmlns:rte="http://example.com/ns/route">
+        /rte:routing/rte:ipv4/rte:route[rte:address='192.0.2.57']
+    </locked-node>
+</nc:rpc-reply>"#,
+            r#"<rpc-reply message-id="102" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+        let lock = conn
+            .partial_lock(
+                &["/rte:routing/rte:ipv4/rte:route"],
+                &[("rte", "http://example.com/ns/route")],
+            )
+            .unwrap();
+        assert_eq!(lock.lock_id(), 127);
+        assert_eq!(
+            lock.locked_nodes(),
+            &[
+                "/rte:routing/rte:ipv4/rte:route[rte:address='192.0.2.1']",
+                "/rte:routing/rte:ipv4/rte:route[rte:address='192.0.2.57']"
+            ]
+        );
+        drop(lock);
+
+        let written = written.lock().unwrap();
+        assert!(written[1].contains(
+            r#"<select xmlns:rte="http://example.com/ns/route">/rte:routing/rte:ipv4/rte:route</select>"#
+        ));
+        assert!(written[2].contains("<lock-id>127</lock-id>"));
+    }
+}
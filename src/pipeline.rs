@@ -0,0 +1,361 @@
+//! RPC pipelining
+//!
+//! A client may send several `<rpc>` requests without waiting for the
+//! replies in between. RFC 6241 requires the server to process them and send
+//! the replies in the order the requests were received, so the replies can be
+//! matched back by their `message-id`. This saves one round trip per request
+//! on high-latency links.
+
+use crate::error::{check_reply, is_rpc_error};
+use crate::framing::{framing_error, is_recoverable};
+use crate::{rpc_xml, Connection};
+use log::*;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Handle of an RPC sent with [`Connection::send_rpc`]
+///
+/// Pass it to [`Connection::wait_reply`] to retrieve the reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[must_use = "the reply has to be collected with Connection::wait_reply"]
+pub struct PendingReply {
+    message_id: u64,
+}
+
+impl PendingReply {
+    /// The `message-id` attribute of the request
+    pub fn message_id(&self) -> u64 {
+        self.message_id
+    }
+}
+
+/// Find the start tag of the root element of a message
+///
+/// Returns the local name of the element and the rest of the start tag.
+pub(crate) fn root_element(msg: &str) -> Option<(&str, &str)> {
+    let mut rest = msg;
+    loop {
+        rest = &rest[rest.find('<')? + 1..];
+        if rest.starts_with('?') || rest.starts_with('!') {
+            continue;
+        }
+        let tag = &rest[..rest.find('>')?];
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        let name = &tag[..name_end];
+        let local_name = name.rsplit(':').next().unwrap_or(name);
+        return Some((local_name, &tag[name_end..]));
+    }
+}
+
+/// Extract the `message-id` attribute of an `<rpc-reply>`
+pub(crate) fn reply_message_id(msg: &str) -> Option<u64> {
+    let (name, attributes) = root_element(msg)?;
+    if name != "rpc-reply" {
+        return None;
+    }
+    let mut rest = attributes;
+    while let Some(pos) = rest.find("message-id") {
+        let before = &rest[..pos];
+        let after = rest[pos + "message-id".len()..].trim_start();
+        rest = after;
+        // only accept `message-id` or `prefix:message-id` as attribute name
+        if !(before.ends_with(char::is_whitespace) || before.ends_with(':')) {
+            continue;
+        }
+        let value = match after.strip_prefix('=') {
+            Some(value) => value.trim_start(),
+            None => continue,
+        };
+        let quote = value.chars().next()?;
+        let value = &value[1..];
+        return value[..value.find(quote)?].trim().parse().ok();
+    }
+    None
+}
+
+impl Connection {
+    /// Send an RPC without waiting for its reply
+    ///
+    /// Any number of RPCs can be outstanding at the same time. Their replies
+    /// are collected with [`Connection::wait_reply`] in any order.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # use netconf_rs::Connection;
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// let config = conn.send_rpc("<get-config><source><running/></source></get-config>")?;
+    /// let state = conn.send_rpc("<get><filter type=\"subtree\"><netconf-state \
+    ///     xmlns=\"urn:ietf:params:xml:ns:yang:ietf-netconf-monitoring\"/></filter></get>")?;
+    /// let config = conn.wait_reply(config)?;
+    /// let state = conn.wait_reply(state)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn send_rpc(&mut self, operation: &str) -> io::Result<PendingReply> {
+        self.message_id += 1;
+        self.write_message(&rpc_xml(self.message_id, operation))?;
+        self.outstanding.push_back(self.message_id);
+        Ok(PendingReply {
+            message_id: self.message_id,
+        })
+    }
+
+    /// Wait for the reply of an RPC sent with [`Connection::send_rpc`]
+    ///
+    /// Replies to other outstanding RPCs that arrive first are kept until
+    /// they are asked for. Errors returned by the server are reported as an
+    /// [`crate::error::RpcError`].
+    ///
+    /// The default RPC timeout of the connection applies, see
+    /// [`Connection::set_rpc_timeout`].
+    pub fn wait_reply(&mut self, pending: PendingReply) -> io::Result<String> {
+        self.wait_reply_timeout(pending, self.rpc_timeout())
+    }
+
+    /// Wait for the reply of an RPC for at most `timeout`
+    ///
+    /// If the reply does not arrive in time, `io::ErrorKind::TimedOut` is
+    /// returned and the RPC is abandoned: its reply is discarded when it
+    /// arrives later.
+    pub fn wait_reply_timeout(
+        &mut self,
+        pending: PendingReply,
+        timeout: Option<Duration>,
+    ) -> io::Result<String> {
+        let message_id = pending.message_id;
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(resp) = self.replies.remove(&message_id) {
+                check_reply(&resp)?;
+                return Ok(resp);
+            }
+            if let Some(error) = self.rejected.remove(&message_id) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+            }
+            if !self.outstanding.contains(&message_id) || self.abandoned.contains(&message_id) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("No outstanding RPC with message-id {}", message_id),
+                ));
+            }
+            match self.read_reply(deadline) {
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                    self.abandoned.insert(message_id);
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!(
+                            "No reply to RPC with message-id {} within {:?}",
+                            message_id,
+                            timeout.unwrap_or_default()
+                        ),
+                    ));
+                }
+                res => res?,
+            }
+        }
+    }
+
+    /// Send several RPCs at once and wait for all of their replies
+    ///
+    /// Errors returned by the server and replies rejected by the framer only
+    /// fail their own RPC.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the result of each RPC in order, or an
+    /// `io::Error` if the transport failed
+    pub fn rpc_batch(&mut self, operations: &[&str]) -> io::Result<Vec<io::Result<String>>> {
+        let pending = operations
+            .iter()
+            .map(|operation| self.send_rpc(operation))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut res = Vec::with_capacity(pending.len());
+        for pending in pending {
+            match self.wait_reply(pending) {
+                Err(e) if !is_rpc_error(&e) && !is_recoverable(&e) => return Err(e),
+                reply => res.push(reply),
+            }
+        }
+        Ok(res)
+    }
+
+    /// Read the next reply from the transport and file it by message-id
+    pub(crate) fn read_reply(&mut self, deadline: Option<Instant>) -> io::Result<()> {
+        let resp = match self.read_message(deadline) {
+            Ok(resp) => resp,
+            Err(e) => match framing_error(&e) {
+                // the rejected message is most likely the reply to the oldest request
+                Some(error) if error.is_recoverable() && !self.outstanding.is_empty() => {
+                    let message_id = self.outstanding.pop_front().unwrap();
+                    warn!("Rejected reply to message-id {}: {}", message_id, error);
+                    if !self.abandoned.remove(&message_id) {
+                        self.rejected.insert(message_id, error.clone());
+                    }
+                    return Ok(());
+                }
+                _ => return Err(e),
+            },
+        };
+        match root_element(&resp) {
+            Some(("rpc-reply", _)) => {}
+            _ => {
+                warn!("Ignoring unexpected message: {}", resp);
+                return Ok(());
+            }
+        }
+        // replies come in request order, so a reply without a
+        // message-id belongs to the oldest outstanding request
+        let message_id = match reply_message_id(&resp) {
+            Some(id) => id,
+            None => *self.outstanding.front().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Unexpected rpc-reply")
+            })?,
+        };
+        match self.outstanding.iter().position(|id| *id == message_id) {
+            Some(pos) => {
+                self.outstanding.remove(pos);
+                if self.abandoned.remove(&message_id) {
+                    debug!("Discarding late reply to message-id {}", message_id);
+                } else {
+                    self.replies.insert(message_id, resp);
+                }
+            }
+            None => warn!("Ignoring reply with unknown message-id {}", message_id),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RpcError;
+    use crate::framing::FramingError;
+    use crate::transport::mock::MockTransport;
+
+    #[test]
+    fn parse_message_id() {
+        assert_eq!(
+            reply_message_id(
+                r#"<?xml version="1.0"?><rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#
+            ),
+            Some(101)
+        );
+        assert_eq!(
+            reply_message_id(
+                r#"<nc:rpc-reply xmlns:nc="urn:ietf:params:xml:ns:netconf:base:1.0" nc:message-id='7'/>"#
+            ),
+            Some(7)
+        );
+        assert_eq!(
+            reply_message_id(r#"<notification><eventTime/></notification>"#),
+            None
+        );
+    }
+
+    #[test]
+    fn pipelined_replies() {
+        let (transport, written) = MockTransport::new(&[
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>first</data></rpc-reply>"#,
+            r#"<rpc-reply message-id="102" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <rpc-error>
+        <error-type>application</error-type>
+        <error-tag>invalid-value</error-tag>
+        <error-severity>error</error-severity>
+    </rpc-error>
+</rpc-reply>"#,
+            r#"<rpc-reply message-id="103" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>third</data></rpc-reply>"#,
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+        let first = conn.send_rpc("<get/>").unwrap();
+        let second = conn.send_rpc("<get/>").unwrap();
+        let third = conn.send_rpc("<get/>").unwrap();
+        assert_eq!(written.lock().unwrap().len(), 4);
+
+        assert!(conn.wait_reply(third).unwrap().contains("third"));
+        assert!(conn.wait_reply(first).unwrap().contains("first"));
+        let err = conn.wait_reply(second).unwrap_err();
+        assert_eq!(
+            err.get_ref()
+                .unwrap()
+                .downcast_ref::<RpcError>()
+                .unwrap()
+                .error_tag,
+            "invalid-value"
+        );
+        assert_eq!(
+            conn.wait_reply(first).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn rpc_timeout() {
+        let (transport, _) = MockTransport::new(&[
+            "",
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>late</data></rpc-reply>"#,
+            r#"<rpc-reply message-id="102" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>second</data></rpc-reply>"#,
+        ]);
+        let mut conn =
+            Connection::with_rpc_timeout(transport, Some(Duration::from_secs(5))).unwrap();
+        let first = conn.send_rpc("<get/>").unwrap();
+        assert_eq!(
+            conn.wait_reply(first).unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        assert!(conn
+            .rpc_with_timeout("<get/>", None)
+            .unwrap()
+            .contains("second"));
+        assert_eq!(
+            conn.wait_reply(first).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn writes_without_read_timeout() {
+        let (transport, _) = MockTransport::new(&[
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+            r#"<rpc-reply message-id="102" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+        ]);
+        let write_timeouts = transport.write_timeouts();
+        let mut conn =
+            Connection::with_rpc_timeout(transport, Some(Duration::from_secs(5))).unwrap();
+        conn.rpc("<commit/>").unwrap();
+        conn.rpc("<commit/>").unwrap();
+        // the hello and both RPCs, none bounded by the timeout of the previous read
+        assert_eq!(*write_timeouts.lock().unwrap(), vec![None; 3]);
+    }
+
+    #[test]
+    fn oversized_reply() {
+        let huge = format!(
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>{}</data></rpc-reply>"#,
+            "<route/>".repeat(100)
+        );
+        let (transport, _) = MockTransport::new(&[
+            &huge,
+            r#"<rpc-reply message-id="102" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+        conn.set_max_message_size(Some(200));
+        let first = conn.send_rpc("<get/>").unwrap();
+        let second = conn.send_rpc("<commit/>").unwrap();
+        assert!(conn.wait_reply(second).unwrap().contains("<ok/>"));
+        let err = conn.wait_reply(first).unwrap_err();
+        assert_eq!(
+            framing_error(&err),
+            Some(&FramingError::MessageTooLarge { limit: 200 })
+        );
+        assert!(!conn.has_failed());
+    }
+}
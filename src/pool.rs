@@ -0,0 +1,443 @@
+//! Connection pool
+//!
+//! [`SessionPool`] keeps established [`Connection`]s per device and hands
+//! them out again, saving the SSH handshake and hello exchange of a fresh
+//! connection. The number of sessions per device is limited, so devices with
+//! a small session limit are not exhausted, and sessions that stay idle for
+//! too long are closed.
+//!
+//! A pooled connection is returned to the pool when the [`PooledConnection`]
+//! guard is dropped, unless its transport has failed.
+
+use crate::keepalive::DEFAULT_PROBE_TIMEOUT;
+use crate::transport::Transport;
+use crate::Connection;
+use log::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Configuration for a session pool
+///
+/// # Example
+///
+/// ```
+/// use netconf_rs::pool::PoolConfig;
+/// use std::time::Duration;
+///
+/// let config = PoolConfig::new()
+///     .max_per_device(4)
+///     .idle_timeout(Duration::from_secs(120))
+///     .rpc_timeout(Duration::from_secs(30));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Maximum number of sessions per device, idle or in use
+    pub max_per_device: usize,
+    /// Close sessions that stay idle for longer than this
+    pub idle_timeout: Option<Duration>,
+    /// Probe sessions that stayed idle for longer than this before handing them out
+    pub health_check_after: Option<Duration>,
+    /// How long the probe of an idle session waits for its reply, see
+    /// [`Connection::is_alive`]
+    pub health_check_timeout: Duration,
+    /// How long to wait for a session when a device is at its limit
+    pub wait_timeout: Duration,
+    /// Default RPC timeout of new connections
+    pub rpc_timeout: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_per_device: 2,
+            idle_timeout: Some(Duration::from_secs(300)),
+            health_check_after: Some(Duration::from_secs(30)),
+            health_check_timeout: DEFAULT_PROBE_TIMEOUT,
+            wait_timeout: Duration::from_secs(30),
+            rpc_timeout: None,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Create a new pool configuration with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of sessions per device
+    pub fn max_per_device(mut self, max: usize) -> Self {
+        self.max_per_device = max;
+        self
+    }
+
+    /// Close sessions that stay idle for longer than `timeout`
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Probe sessions that stayed idle for longer than `idle` before reuse
+    pub fn health_check_after(mut self, idle: Duration) -> Self {
+        self.health_check_after = Some(idle);
+        self
+    }
+
+    /// Set how long the probe of an idle session waits for its reply
+    pub fn health_check_timeout(mut self, timeout: Duration) -> Self {
+        self.health_check_timeout = timeout;
+        self
+    }
+
+    /// Set how long to wait for a session when a device is at its limit
+    pub fn wait_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Set the default RPC timeout of new connections
+    pub fn rpc_timeout(mut self, timeout: Duration) -> Self {
+        self.rpc_timeout = Some(timeout);
+        self
+    }
+}
+
+/// Sessions of one device
+struct DeviceSessions {
+    /// Idle connections with the time they were returned, oldest first
+    idle: Vec<(Connection, Instant)>,
+    /// Number of connections handed out or being established
+    in_use: usize,
+}
+
+type Connect<K> = Box<dyn Fn(&K, Option<Duration>) -> io::Result<Connection> + Send + Sync>;
+
+struct Inner<K> {
+    connect: Connect<K>,
+    config: PoolConfig,
+    devices: Mutex<HashMap<K, DeviceSessions>>,
+    returned: Condvar,
+}
+
+/// A pool of NETCONF connections keyed by device
+///
+/// The key identifies a device and the credentials used for it, e.g. a
+/// tuple of address and user name. The pool is cheaply cloneable and can
+/// be shared between threads.
+///
+/// # Examples
+///
+/// ```ignore
+/// use netconf_rs::pool::{PoolConfig, SessionPool};
+/// use netconf_rs::transport::ssh::SSHTransport;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let pool = SessionPool::new(
+///     |(addr, user): &(String, String)| SSHTransport::connect(addr, user, "password"),
+///     PoolConfig::default(),
+/// );
+///
+/// let device = (String::from("192.168.1.1:830"), String::from("admin"));
+/// let mut conn = pool.get(&device)?;
+/// let config = conn.get_config()?;
+/// // the connection goes back to the pool here
+/// drop(conn);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// *Note: This example requires the `ssh2` feature to be enabled.*
+pub struct SessionPool<K> {
+    inner: Arc<Inner<K>>,
+}
+
+impl<K> Clone for SessionPool<K> {
+    fn clone(&self) -> Self {
+        SessionPool {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> SessionPool<K> {
+    /// Create an empty pool
+    ///
+    /// # Arguments
+    ///
+    /// * `factory` - Creates a new transport to the device identified by a key
+    /// * `config` - Pool options
+    pub fn new<T, F>(factory: F, config: PoolConfig) -> SessionPool<K>
+    where
+        T: Transport + 'static,
+        F: Fn(&K) -> io::Result<T> + Send + Sync + 'static,
+    {
+        SessionPool {
+            inner: Arc::new(Inner {
+                connect: Box::new(move |key, timeout| {
+                    Connection::with_rpc_timeout(factory(key)?, timeout)
+                }),
+                config,
+                devices: Mutex::new(HashMap::new()),
+                returned: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Get a connection to a device
+    ///
+    /// An idle connection is reused if there is one, otherwise a new one is
+    /// established. If the device already has the maximum number of
+    /// sessions in use, this waits for one to be returned.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the connection guard, or an `io::Error` if no
+    /// connection could be established or the wait timed out
+    pub fn get(&self, key: &K) -> io::Result<PooledConnection<K>> {
+        let config = &self.inner.config;
+        let deadline = Instant::now() + config.wait_timeout;
+        let mut devices = self.devices();
+        loop {
+            let device = devices.entry(key.clone()).or_insert(DeviceSessions {
+                idle: Vec::new(),
+                in_use: 0,
+            });
+            if let Some(timeout) = config.idle_timeout {
+                device.idle.retain(|(_, since)| since.elapsed() < timeout);
+            }
+            if let Some((mut connection, since)) = device.idle.pop() {
+                device.in_use += 1;
+                drop(devices);
+                let healthy = match config.health_check_after {
+                    Some(after) if since.elapsed() >= after => {
+                        connection.set_probe_timeout(config.health_check_timeout);
+                        connection.is_alive()
+                    }
+                    _ => true,
+                };
+                if healthy {
+                    return Ok(self.guard(key, connection));
+                }
+                debug!("Dropping dead pooled connection");
+                devices = self.release(key);
+                continue;
+            }
+            if device.in_use < config.max_per_device {
+                device.in_use += 1;
+                drop(devices);
+                return match (self.inner.connect)(key, config.rpc_timeout) {
+                    Ok(connection) => Ok(self.guard(key, connection)),
+                    Err(e) => {
+                        drop(self.release(key));
+                        Err(e)
+                    }
+                };
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "Timed out waiting for a pooled connection",
+                ));
+            }
+            devices = self
+                .inner
+                .returned
+                .wait_timeout(devices, remaining)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Close the connections that stayed idle for longer than the idle timeout
+    ///
+    /// Expired connections are also closed whenever a device is asked for;
+    /// call this periodically to close them for devices that are not.
+    pub fn evict_idle(&self) {
+        if let Some(timeout) = self.inner.config.idle_timeout {
+            let mut devices = self.devices();
+            for device in devices.values_mut() {
+                device.idle.retain(|(_, since)| since.elapsed() < timeout);
+            }
+            devices.retain(|_, device| device.in_use > 0 || !device.idle.is_empty());
+        }
+    }
+
+    /// Number of idle connections to a device
+    pub fn idle_count(&self, key: &K) -> usize {
+        self.devices()
+            .get(key)
+            .map_or(0, |device| device.idle.len())
+    }
+
+    fn devices(&self) -> MutexGuard<'_, HashMap<K, DeviceSessions>> {
+        self.inner.devices.lock().unwrap()
+    }
+
+    fn guard(&self, key: &K, connection: Connection) -> PooledConnection<K> {
+        PooledConnection {
+            pool: self.clone(),
+            key: key.clone(),
+            connection: Some(connection),
+        }
+    }
+
+    /// Give up a slot of a device and wake up the waiting callers
+    ///
+    /// All devices share one condition variable, so every waiter is woken up
+    /// to check whether the slot belongs to its device.
+    fn release(&self, key: &K) -> MutexGuard<'_, HashMap<K, DeviceSessions>> {
+        let mut devices = self.devices();
+        if let Some(device) = devices.get_mut(key) {
+            device.in_use -= 1;
+        }
+        self.inner.returned.notify_all();
+        devices
+    }
+}
+
+/// A connection borrowed from a [`SessionPool`]
+///
+/// Dereferences to [`Connection`]. The connection is returned to the pool
+/// when the guard is dropped.
+pub struct PooledConnection<K: Hash + Eq + Clone> {
+    pool: SessionPool<K>,
+    key: K,
+    connection: Option<Connection>,
+}
+
+impl<K: Hash + Eq + Clone> PooledConnection<K> {
+    /// Take the connection out of the pool for good
+    ///
+    /// The device gets a free slot in the pool again.
+    pub fn detach(mut self) -> Connection {
+        self.connection.take().unwrap()
+    }
+}
+
+impl<K: Hash + Eq + Clone> Deref for PooledConnection<K> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl<K: Hash + Eq + Clone> DerefMut for PooledConnection<K> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.connection.as_mut().unwrap()
+    }
+}
+
+impl<K: Hash + Eq + Clone> Drop for PooledConnection<K> {
+    fn drop(&mut self) {
+        let mut devices = self.pool.release(&self.key);
+        match self.connection.take() {
+            Some(connection) if !connection.has_failed() => {
+                if let Some(device) = devices.get_mut(&self.key) {
+                    device.idle.push((connection, Instant::now()));
+                }
+            }
+            Some(_) => debug!("Not returning failed connection to the pool"),
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::mock::MockTransport;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn reuse_connections() {
+        let connects = Arc::new(AtomicUsize::new(0));
+        let counter = connects.clone();
+        let pool = SessionPool::new(
+            move |_: &&str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(MockTransport::new(&[
+                    r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+                ])
+                .0)
+            },
+            PoolConfig::new()
+                .max_per_device(1)
+                .wait_timeout(Duration::ZERO),
+        );
+
+        let mut conn = pool.get(&"switch-1").unwrap();
+        assert_eq!(
+            pool.get(&"switch-1").err().unwrap().kind(),
+            io::ErrorKind::TimedOut
+        );
+        conn.rpc("<commit/>").unwrap();
+        drop(conn);
+        assert_eq!(pool.idle_count(&"switch-1"), 1);
+
+        // reused, then fails as the mock has no more replies
+        let mut conn = pool.get(&"switch-1").unwrap();
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+        assert!(conn.rpc("<commit/>").is_err());
+        drop(conn);
+        assert_eq!(pool.idle_count(&"switch-1"), 0);
+
+        let _other = pool.get(&"switch-2").unwrap();
+        let _conn = pool.get(&"switch-1").unwrap();
+        assert_eq!(connects.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn health_check_dead_session() {
+        let connects = Arc::new(AtomicUsize::new(0));
+        let counter = connects.clone();
+        let pool = SessionPool::new(
+            move |_: &&str| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                // a session silently dropped by a firewall never answers
+                Ok(MockTransport::new(&[""]).0)
+            },
+            PoolConfig::new().health_check_after(Duration::ZERO),
+        );
+
+        drop(pool.get(&"switch-1").unwrap());
+        assert_eq!(pool.idle_count(&"switch-1"), 1);
+        // the probe times out instead of blocking, a new session is established
+        let _conn = pool.get(&"switch-1").unwrap();
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+        assert_eq!(pool.idle_count(&"switch-1"), 0);
+    }
+
+    #[test]
+    fn wake_waiter_of_other_device() {
+        let pool = SessionPool::new(
+            |_: &&str| Ok(MockTransport::new(&[]).0),
+            PoolConfig::new()
+                .max_per_device(1)
+                .wait_timeout(Duration::from_secs(5)),
+        );
+        let a = pool.get(&"switch-a").unwrap();
+        let b = pool.get(&"switch-b").unwrap();
+        let wait = |key: &'static str| {
+            let pool = pool.clone();
+            let waiter = std::thread::spawn(move || pool.get(&key).map(drop));
+            std::thread::sleep(Duration::from_millis(100));
+            waiter
+        };
+        // the waiter for switch-b waits first, returning switch-a must not
+        // only wake it up
+        let waiter_b = wait("switch-b");
+        let waiter_a = wait("switch-a");
+        let start = Instant::now();
+        drop(a);
+        waiter_a.join().unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(2));
+        drop(b);
+        waiter_b.join().unwrap().unwrap();
+    }
+}
@@ -0,0 +1,505 @@
+//! Automatic reconnect
+//!
+//! [`ReconnectingConnection`] wraps a [`Connection`] and re-establishes the
+//! session after the transport failed, e.g. because the device reloaded or
+//! the link went down. A new transport is created with the factory given at
+//! construction, followed by a new hello exchange. Attempts are spaced with
+//! exponential backoff and jitter, and idempotent RPCs (`<get>` and
+//! `<get-config>`) can be retried transparently on the new session.
+//!
+//! State held by the server, such as locks or the candidate datastore, is
+//! lost with the session and is not restored.
+
+use crate::keepalive::DEFAULT_PROBE_TIMEOUT;
+use crate::pipeline::root_element;
+use crate::transport::Transport;
+use crate::{Connection, GET_CONFIG};
+use log::*;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Configuration for reconnecting
+///
+/// # Example
+///
+/// ```
+/// use netconf_rs::reconnect::ReconnectConfig;
+/// use std::time::Duration;
+///
+/// let config = ReconnectConfig::new()
+///     .initial_backoff(Duration::from_millis(500))
+///     .max_backoff(Duration::from_secs(30))
+///     .max_attempts(Some(20));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReconnectConfig {
+    /// Delay before the first reconnect attempt, doubled for every further attempt
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between attempts
+    pub max_backoff: Duration,
+    /// Number of attempts before giving up, `None` to retry forever
+    pub max_attempts: Option<u32>,
+    /// Randomize the delays so many clients do not reconnect in lockstep
+    pub jitter: bool,
+    /// Retry `<get>` and `<get-config>` once the session is re-established
+    pub retry_idempotent: bool,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_attempts: Some(10),
+            jitter: true,
+            retry_idempotent: true,
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// Create a new reconnect configuration with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the delay before the first reconnect attempt
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Set the upper bound of the delay between attempts
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Set the number of attempts before giving up, `None` to retry forever
+    pub fn max_attempts(mut self, attempts: Option<u32>) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Enable or disable randomized delays
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Enable or disable retrying idempotent RPCs after a reconnect
+    pub fn retry_idempotent(mut self, retry: bool) -> Self {
+        self.retry_idempotent = retry;
+        self
+    }
+
+    /// Delay before the given attempt, counting from 1
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .initial_backoff
+            .checked_mul(1 << (attempt - 1).min(31))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+        if self.jitter {
+            jitter(delay)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Pick a random delay between half and all of `delay`
+fn jitter(delay: Duration) -> Duration {
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    let half = delay / 2;
+    let range = (delay - half).as_nanos() as u64;
+    half + Duration::from_nanos(hasher.finish() % (range + 1))
+}
+
+/// Changes of the session state reported by [`ReconnectingConnection`]
+#[derive(Debug)]
+pub enum ConnectionEvent {
+    /// The session was lost
+    Disconnected {
+        /// The error that revealed the failure
+        error: io::Error,
+    },
+    /// A reconnect attempt is made after waiting for `delay`
+    Reconnecting {
+        /// Number of the attempt, counting from 1
+        attempt: u32,
+        /// Delay before the attempt
+        delay: Duration,
+    },
+    /// A reconnect attempt failed
+    ReconnectFailed {
+        /// Number of the attempt, counting from 1
+        attempt: u32,
+        /// Why the attempt failed
+        error: io::Error,
+    },
+    /// The session was re-established
+    Reconnected {
+        /// Number of the successful attempt, counting from 1
+        attempt: u32,
+    },
+}
+
+/// Settings of the connection, applied again to every new session
+#[derive(Debug, Clone)]
+struct Settings {
+    rpc_timeout: Option<Duration>,
+    max_message_size: Option<usize>,
+    max_chunk_size: Option<usize>,
+    probe_timeout: Duration,
+    keepalive_interval: Option<Duration>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            rpc_timeout: None,
+            max_message_size: None,
+            max_chunk_size: None,
+            probe_timeout: DEFAULT_PROBE_TIMEOUT,
+            keepalive_interval: None,
+        }
+    }
+}
+
+impl Settings {
+    fn apply(&self, connection: &mut Connection) {
+        connection.set_rpc_timeout(self.rpc_timeout);
+        connection.set_max_message_size(self.max_message_size);
+        connection.set_max_chunk_size(self.max_chunk_size);
+        connection.set_probe_timeout(self.probe_timeout);
+        connection.set_keepalive_interval(self.keepalive_interval);
+    }
+}
+
+type Connect = Box<dyn FnMut(&Settings) -> io::Result<Connection> + Send>;
+type EventHandler = Box<dyn FnMut(&ConnectionEvent) + Send>;
+
+/// A NETCONF connection that reconnects after transport failures
+///
+/// # Examples
+///
+/// ```ignore
+/// use netconf_rs::reconnect::{ConnectionEvent, ReconnectConfig, ReconnectingConnection};
+/// use netconf_rs::transport::ssh::SSHTransport;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let mut conn = ReconnectingConnection::new(
+///     || SSHTransport::connect("192.168.1.1:830", "admin", "password"),
+///     ReconnectConfig::default(),
+/// )?
+/// .on_event(|event| match event {
+///     ConnectionEvent::Disconnected { error } => eprintln!("session lost: {}", error),
+///     _ => {}
+/// });
+///
+/// let config = conn.get_config()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// *Note: This example requires the `ssh2` feature to be enabled.*
+pub struct ReconnectingConnection {
+    connect: Connect,
+    connection: Option<Connection>,
+    config: ReconnectConfig,
+    settings: Settings,
+    on_event: Option<EventHandler>,
+}
+
+impl ReconnectingConnection {
+    /// Connect to a NETCONF server with a transport factory
+    ///
+    /// The factory is called once now and again for every reconnect attempt.
+    ///
+    /// # Arguments
+    ///
+    /// * `factory` - Creates a new transport to the server
+    /// * `config` - Reconnect options
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the connection, or an `io::Error` if the first
+    /// connection attempt fails
+    pub fn new<T, F>(mut factory: F, config: ReconnectConfig) -> io::Result<ReconnectingConnection>
+    where
+        T: Transport + 'static,
+        F: FnMut() -> io::Result<T> + Send + 'static,
+    {
+        let mut connect: Connect = Box::new(move |settings: &Settings| {
+            let mut connection = Connection::with_rpc_timeout(factory()?, settings.rpc_timeout)?;
+            settings.apply(&mut connection);
+            Ok(connection)
+        });
+        let settings = Settings::default();
+        let connection = connect(&settings)?;
+        Ok(ReconnectingConnection {
+            connect,
+            connection: Some(connection),
+            config,
+            settings,
+            on_event: None,
+        })
+    }
+
+    /// Register a callback for changes of the session state
+    pub fn on_event(mut self, handler: impl FnMut(&ConnectionEvent) + Send + 'static) -> Self {
+        self.on_event = Some(Box::new(handler));
+        self
+    }
+
+    /// Set the default RPC timeout, kept across reconnects
+    ///
+    /// See [`Connection::set_rpc_timeout`].
+    pub fn set_rpc_timeout(&mut self, timeout: Option<Duration>) {
+        self.settings.rpc_timeout = timeout;
+        self.apply_settings();
+    }
+
+    /// Limit the size of incoming messages, kept across reconnects
+    ///
+    /// See [`Connection::set_max_message_size`].
+    pub fn set_max_message_size(&mut self, limit: Option<usize>) {
+        self.settings.max_message_size = limit;
+        self.apply_settings();
+    }
+
+    /// Limit the size of incoming chunks, kept across reconnects
+    ///
+    /// See [`Connection::set_max_chunk_size`].
+    pub fn set_max_chunk_size(&mut self, limit: Option<usize>) {
+        self.settings.max_chunk_size = limit;
+        self.apply_settings();
+    }
+
+    /// Set how long a probe waits for its reply, kept across reconnects
+    ///
+    /// See [`Connection::set_probe_timeout`].
+    pub fn set_probe_timeout(&mut self, timeout: Duration) {
+        self.settings.probe_timeout = timeout;
+        self.apply_settings();
+    }
+
+    /// Probe the session when idle for at least this interval, kept across reconnects
+    ///
+    /// See [`Connection::set_keepalive_interval`].
+    pub fn set_keepalive_interval(&mut self, interval: Option<Duration>) {
+        self.settings.keepalive_interval = interval;
+        self.apply_settings();
+    }
+
+    fn apply_settings(&mut self) {
+        if let Some(connection) = &mut self.connection {
+            self.settings.apply(connection);
+        }
+    }
+
+    /// Whether a usable session is currently established
+    pub fn is_connected(&self) -> bool {
+        self.connection
+            .as_ref()
+            .is_some_and(|connection| !connection.has_failed())
+    }
+
+    /// The underlying connection, reconnecting first if the session was lost
+    ///
+    /// Use this for operations without a wrapper here. Failures are noticed
+    /// and repaired on the next call. Settings changed on the returned
+    /// connection are lost on reconnect, use the setters of
+    /// `ReconnectingConnection` to keep them.
+    pub fn connection(&mut self) -> io::Result<&mut Connection> {
+        if self
+            .connection
+            .as_ref()
+            .is_some_and(|connection| connection.has_failed())
+        {
+            self.disconnected(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "NETCONF transport failed",
+            ));
+        }
+        if self.connection.is_none() {
+            self.reconnect()?;
+        }
+        Ok(self.connection.as_mut().unwrap())
+    }
+
+    /// Sends an RPC and waits for its reply
+    ///
+    /// If the session is lost during an idempotent RPC and
+    /// [`ReconnectConfig::retry_idempotent`] is set, the session is
+    /// re-established and the RPC is sent again. Otherwise the error is
+    /// returned and the session is re-established on the next call.
+    ///
+    /// See [`Connection::rpc`].
+    pub fn rpc(&mut self, operation: &str) -> io::Result<String> {
+        let connection = self.connection()?;
+        match connection.rpc(operation) {
+            Err(e) if connection.has_failed() => {
+                self.disconnected(io::Error::new(e.kind(), e.to_string()));
+                if !(self.config.retry_idempotent && is_idempotent(operation)) {
+                    return Err(e);
+                }
+                debug!("Retrying idempotent RPC after reconnect");
+                self.connection()?.rpc(operation)
+            }
+            res => res,
+        }
+    }
+
+    /// Retrieves the running configuration from the NETCONF server
+    pub fn get_config(&mut self) -> io::Result<String> {
+        self.rpc(GET_CONFIG)
+    }
+
+    /// Re-establish the session now
+    ///
+    /// Makes up to [`ReconnectConfig::max_attempts`] attempts with
+    /// exponential backoff between them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or the `io::Error` of the last attempt
+    pub fn reconnect(&mut self) -> io::Result<()> {
+        self.connection = None;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let delay = self.config.backoff(attempt);
+            self.emit(ConnectionEvent::Reconnecting { attempt, delay });
+            std::thread::sleep(delay);
+            match (self.connect)(&self.settings) {
+                Ok(connection) => {
+                    info!("NETCONF session re-established after {} attempts", attempt);
+                    self.connection = Some(connection);
+                    self.emit(ConnectionEvent::Reconnected { attempt });
+                    return Ok(());
+                }
+                Err(e) => {
+                    warn!("Reconnect attempt {} failed: {}", attempt, e);
+                    let last = self.config.max_attempts.is_some_and(|max| attempt >= max);
+                    let res = io::Error::new(e.kind(), e.to_string());
+                    self.emit(ConnectionEvent::ReconnectFailed { attempt, error: e });
+                    if last {
+                        return Err(res);
+                    }
+                }
+            }
+        }
+    }
+
+    fn disconnected(&mut self, error: io::Error) {
+        warn!("NETCONF session lost: {}", error);
+        self.connection = None;
+        self.emit(ConnectionEvent::Disconnected { error });
+    }
+
+    fn emit(&mut self, event: ConnectionEvent) {
+        if let Some(handler) = &mut self.on_event {
+            handler(&event);
+        }
+    }
+}
+
+/// Whether an operation can be sent again without changing its outcome
+fn is_idempotent(operation: &str) -> bool {
+    matches!(root_element(operation), Some(("get" | "get-config", _)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::mock::MockTransport;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn backoff() {
+        let config = ReconnectConfig::new().jitter(false);
+        assert_eq!(config.backoff(1), Duration::from_secs(1));
+        assert_eq!(config.backoff(4), Duration::from_secs(8));
+        assert_eq!(config.backoff(100), Duration::from_secs(60));
+        let delay = ReconnectConfig::new().backoff(3);
+        assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(4));
+    }
+
+    #[test]
+    fn reconnect_and_retry() {
+        let mut transports = VecDeque::from([
+            Ok(MockTransport::new(&[]).0),
+            Err(io::Error::new(io::ErrorKind::ConnectionRefused, "refused")),
+            Ok(MockTransport::new(&[
+                r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>config</data></rpc-reply>"#,
+            ])
+            .0),
+        ]);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let mut conn = ReconnectingConnection::new(
+            move || transports.pop_front().unwrap(),
+            ReconnectConfig::new().initial_backoff(Duration::ZERO),
+        )
+        .unwrap()
+        .on_event(move |event| recorded.lock().unwrap().push(format!("{:?}", event)));
+
+        assert!(conn.get_config().unwrap().contains("config"));
+        assert!(conn.is_connected());
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 5);
+        assert!(events[0].starts_with("Disconnected"));
+        assert!(events[2].starts_with("ReconnectFailed { attempt: 1"));
+        assert_eq!(events[4], "Reconnected { attempt: 2 }");
+    }
+
+    #[test]
+    fn no_retry_for_edits() {
+        let mut conn = ReconnectingConnection::new(
+            || Ok(MockTransport::new(&[]).0),
+            ReconnectConfig::new().initial_backoff(Duration::ZERO),
+        )
+        .unwrap();
+        assert_eq!(
+            conn.rpc("<edit-config/>").unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert!(!conn.is_connected());
+    }
+
+    #[test]
+    fn keep_settings() {
+        let huge = format!(
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>{}</data></rpc-reply>"#,
+            "<route/>".repeat(100)
+        );
+        let mut transports = VecDeque::from([
+            MockTransport::new(&[]).0,
+            MockTransport::new(&[&huge, ""]).0,
+        ]);
+        let mut conn = ReconnectingConnection::new(
+            move || Ok(transports.pop_front().unwrap()),
+            ReconnectConfig::new().initial_backoff(Duration::ZERO),
+        )
+        .unwrap();
+        conn.set_max_message_size(Some(200));
+        conn.set_keepalive_interval(Some(Duration::ZERO));
+        conn.reconnect().unwrap();
+
+        let err = conn.get_config().unwrap_err();
+        assert!(crate::framing::framing_error(&err).is_some());
+        // the keepalive interval was kept, so a probe is sent
+        assert_eq!(
+            conn.connection().unwrap().keepalive().unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+    }
+}
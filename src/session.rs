@@ -0,0 +1,313 @@
+//! Shared NETCONF session
+//!
+//! [`SessionHandle`] lets many tasks or threads share one NETCONF session.
+//! A background task owns the transport: it writes the requests submitted by
+//! the handles, reads every incoming message, hands each `<rpc-reply>` to the
+//! caller waiting for its `message-id` and forwards `<notification>` messages
+//! to a separate channel. Callers never wait for each other's round trips.
+//!
+//! *Note: This module requires the `async` feature to be enabled.*
+
+use crate::error::check_reply;
+use crate::framing::{framing_error, negotiate, Framer};
+use crate::monitoring::{
+    get_schema_request, netconf_state_request, parse_netconf_state, reply_data, NetconfState,
+};
+use crate::pipeline::{reply_message_id, root_element};
+use crate::transport::AsyncTransport;
+use crate::{parse_hello, rpc_xml, GET_CONFIG, HELLO};
+use log::*;
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+struct Request {
+    message_id: u64,
+    xml: String,
+    reply: oneshot::Sender<io::Result<String>>,
+}
+
+/// A cheaply cloneable handle to a shared NETCONF session
+///
+/// All clones talk to the same session. The session is closed when the last
+/// handle is dropped and no replies are outstanding.
+///
+/// # Examples
+///
+/// ```ignore
+/// use netconf_rs::session::SessionHandle;
+/// use netconf_rs::transport::russh::{RusshConfig, RusshTransport};
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let transport = RusshTransport::connect_password_async(
+///     "192.168.1.1:830",
+///     "admin",
+///     "password",
+///     &RusshConfig::default(),
+/// )
+/// .await?;
+/// let (session, mut notifications) = SessionHandle::spawn(transport).await?;
+///
+/// let worker = session.clone();
+/// let config = tokio::spawn(async move { worker.get_config().await });
+/// let state = session.get_netconf_state().await?;
+/// let config = config.await.unwrap()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// *Note: This example requires the `russh` feature to be enabled.*
+#[derive(Clone)]
+pub struct SessionHandle {
+    requests: mpsc::UnboundedSender<Request>,
+    message_id: Arc<AtomicU64>,
+}
+
+impl SessionHandle {
+    /// Perform the hello exchange and start the background task on the current runtime
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the handle and a receiver for the `<notification>`
+    /// messages sent by the server, or an `io::Error` if the handshake fails
+    pub async fn spawn(
+        mut transport: impl AsyncTransport + 'static,
+    ) -> io::Result<(SessionHandle, mpsc::UnboundedReceiver<String>)> {
+        debug!("Get capabilities of NetConf server");
+        let mut framer = Framer::new();
+        framer.write_message_async(&mut transport, HELLO).await?;
+        let resp = framer.read_message_async(&mut transport).await?;
+        framer.set_framing(negotiate(&parse_hello(&resp)?));
+
+        let (requests, request_rx) = mpsc::unbounded_channel();
+        let (notification_tx, notifications) = mpsc::unbounded_channel();
+        tokio::spawn(run(
+            Box::new(transport),
+            framer,
+            request_rx,
+            notification_tx,
+        ));
+        Ok((
+            SessionHandle {
+                requests,
+                message_id: Arc::new(AtomicU64::new(100)),
+            },
+            notifications,
+        ))
+    }
+
+    /// Submit an RPC to the background task
+    fn submit(&self, operation: &str) -> io::Result<oneshot::Receiver<io::Result<String>>> {
+        let message_id = self.message_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (reply, reply_rx) = oneshot::channel();
+        self.requests
+            .send(Request {
+                message_id,
+                xml: rpc_xml(message_id, operation),
+                reply,
+            })
+            .map_err(|_| session_closed())?;
+        Ok(reply_rx)
+    }
+
+    /// Sends an RPC to the NETCONF server and waits for its reply
+    ///
+    /// See [`crate::Connection::rpc`].
+    pub async fn rpc(&self, operation: &str) -> io::Result<String> {
+        let resp = self
+            .submit(operation)?
+            .await
+            .map_err(|_| session_closed())??;
+        check_reply(&resp)?;
+        Ok(resp)
+    }
+
+    /// Sends an RPC and blocks the current thread until its reply arrives
+    ///
+    /// This is meant for plain threads sharing the session with async code.
+    /// It must not be called from within an async context.
+    pub fn rpc_blocking(&self, operation: &str) -> io::Result<String> {
+        let resp = self
+            .submit(operation)?
+            .blocking_recv()
+            .map_err(|_| session_closed())??;
+        check_reply(&resp)?;
+        Ok(resp)
+    }
+
+    /// Retrieves the running configuration from the NETCONF server
+    pub async fn get_config(&self) -> io::Result<String> {
+        self.rpc(GET_CONFIG).await
+    }
+
+    /// Retrieve the whole `/netconf-state` container
+    pub async fn get_netconf_state(&self) -> io::Result<NetconfState> {
+        let resp = self.rpc(&netconf_state_request("")).await?;
+        parse_netconf_state(&resp)
+    }
+
+    /// Retrieve a schema from the server with `<get-schema>`
+    pub async fn get_schema(
+        &self,
+        identifier: &str,
+        version: Option<&str>,
+        format: Option<&str>,
+    ) -> io::Result<String> {
+        let resp = self
+            .rpc(&get_schema_request(identifier, version, format))
+            .await?;
+        reply_data(&resp)
+    }
+
+    /// Whether the background task has stopped, e.g. because the transport failed
+    pub fn is_closed(&self) -> bool {
+        self.requests.is_closed()
+    }
+}
+
+fn session_closed() -> io::Error {
+    io::Error::new(io::ErrorKind::NotConnected, "NETCONF session closed")
+}
+
+/// Background task owning the transport
+async fn run(
+    mut transport: Box<dyn AsyncTransport + Send>,
+    mut framer: Framer,
+    mut requests: mpsc::UnboundedReceiver<Request>,
+    notifications: mpsc::UnboundedSender<String>,
+) {
+    let mut pending: HashMap<u64, oneshot::Sender<io::Result<String>>> = HashMap::new();
+    let mut accepting = true;
+    let error = loop {
+        if !accepting && pending.is_empty() {
+            debug!("All session handles dropped, closing session");
+            return;
+        }
+        tokio::select! {
+            request = requests.recv(), if accepting => match request {
+                Some(request) => match framer.write_message_async(&mut transport, &request.xml).await {
+                    Ok(()) => {
+                        pending.insert(request.message_id, request.reply);
+                    }
+                    Err(e) => break e,
+                },
+                None => accepting = false,
+            },
+            msg = framer.read_message_async(&mut transport) => match msg {
+                Ok(msg) => dispatch(msg, &mut pending, &notifications),
+                Err(e) => match framing_error(&e) {
+                    // the framer skips the rejected message, which most
+                    // likely is the reply to the oldest request
+                    Some(error) if error.is_recoverable() => {
+                        warn!("Rejected message: {}", error);
+                        if let Some(message_id) = pending.keys().min().copied() {
+                            let reply = pending.remove(&message_id).unwrap();
+                            let _ = reply.send(Err(e));
+                        }
+                    }
+                    _ => break e,
+                },
+            },
+        }
+    };
+    warn!("NETCONF session failed: {}", error);
+    for (_, reply) in pending.drain() {
+        let _ = reply.send(Err(io::Error::new(error.kind(), error.to_string())));
+    }
+}
+
+fn dispatch(
+    msg: String,
+    pending: &mut HashMap<u64, oneshot::Sender<io::Result<String>>>,
+    notifications: &mpsc::UnboundedSender<String>,
+) {
+    match root_element(&msg) {
+        Some(("rpc-reply", _)) => match reply_message_id(&msg) {
+            Some(message_id) => match pending.remove(&message_id) {
+                Some(reply) => {
+                    let _ = reply.send(Ok(msg));
+                }
+                None => warn!("Ignoring reply with unknown message-id {}", message_id),
+            },
+            None => warn!("Ignoring reply without message-id: {}", msg),
+        },
+        Some(("notification", _)) => {
+            if notifications.send(msg).is_err() {
+                debug!("Dropping notification, receiver is gone");
+            }
+        }
+        _ => warn!("Ignoring unexpected message: {}", msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::{Framing, BASE_1_1};
+    use tokio::io::DuplexStream;
+
+    /// Answer every `<rpc>` with its message-id as data, holding back the
+    /// first reply until the second request arrives
+    async fn server(mut stream: DuplexStream) {
+        let mut framer = Framer::new();
+        framer.read_message_async(&mut stream).await.unwrap();
+        let hello = format!(
+            r#"<hello xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><capabilities><capability>{}</capability></capabilities></hello>"#,
+            BASE_1_1
+        );
+        framer
+            .write_message_async(&mut stream, &hello)
+            .await
+            .unwrap();
+        framer.set_framing(Framing::Chunked);
+        let mut held = None;
+        while let Ok(request) = framer.read_message_async(&mut stream).await {
+            let start = request.find("message-id=\"").unwrap() + 12;
+            let id = &request[start..start + request[start..].find('"').unwrap()];
+            let reply = format!(
+                r#"<rpc-reply message-id="{}" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>{}</data></rpc-reply>"#,
+                id, id
+            );
+            let mut replies = Vec::new();
+            match held.take() {
+                None if id == "101" => held = Some(reply),
+                Some(first) => {
+                    replies.push(reply);
+                    replies.push(String::from(
+                        r#"<notification xmlns="urn:ietf:params:xml:ns:netconf:notification:1.0"><eventTime>2024-01-01T00:00:00Z</eventTime></notification>"#,
+                    ));
+                    replies.push(first);
+                }
+                None => replies.push(reply),
+            }
+            for reply in replies {
+                framer
+                    .write_message_async(&mut stream, &reply)
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn shared_session() {
+        let (client, server_stream) = tokio::io::duplex(4096);
+        tokio::spawn(server(server_stream));
+        let (session, mut notifications) = SessionHandle::spawn(client).await.unwrap();
+
+        let first = {
+            let session = session.clone();
+            tokio::spawn(async move { session.rpc("<get/>").await })
+        };
+        tokio::task::yield_now().await;
+        let second = session.clone().rpc("<get/>").await.unwrap();
+        assert!(second.contains("<data>102</data>"));
+        assert!(first.await.unwrap().unwrap().contains("<data>101</data>"));
+        assert!(notifications.recv().await.unwrap().contains("<eventTime>"));
+
+        drop(session);
+        assert!(notifications.recv().await.is_none());
+    }
+}
@@ -0,0 +1,334 @@
+//! Streaming replies
+//!
+//! [`Connection::rpc`] keeps the whole reply in memory, which is a problem for
+//! huge `<data>` payloads like a full routing table or the configuration of a
+//! large chassis. [`Connection::rpc_stream`] hands back the reply as a
+//! [`BufRead`] instead, yielding the bytes as they arrive from the transport.
+//! Combined with an event based XML parser, e.g. [`ReplyReader::xml_reader`]
+//! with the `quick-xml` feature, the reply is processed with bounded memory.
+
+use crate::framing::is_recoverable;
+use crate::pipeline::{reply_message_id, root_element};
+use crate::transport::read_with_timeout;
+use crate::Connection;
+use log::*;
+use std::io;
+use std::io::{BufRead, Read};
+use std::time::Instant;
+
+impl Connection {
+    /// Send an RPC and stream its reply
+    ///
+    /// The reply is not checked for `<rpc-error>`s, the caller has to handle
+    /// them while parsing. The default RPC timeout of the connection applies
+    /// to every read from the transport, see [`Connection::set_rpc_timeout`].
+    ///
+    /// The limits set with [`Connection::set_max_message_size`] and
+    /// [`Connection::set_max_chunk_size`] apply: reading fails with a
+    /// [`crate::framing::FramingError`] once the reply exceeds them.
+    ///
+    /// Notifications and replies with another `message-id` that arrive
+    /// first are skipped. The session can not be used for other RPCs until
+    /// the reader is dropped. Dropping it before the end of the reply reads
+    /// and discards the rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The XML content of the operation to perform
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the reader of the reply, or an `io::Error` if the
+    /// RPC could not be sent or replies to other RPCs are still outstanding
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # use netconf_rs::Connection;
+    /// use std::io::BufRead;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// let reply = conn.rpc_stream("<get-config><source><running/></source></get-config>")?;
+    /// for line in reply.lines() {
+    ///     println!("{}", line?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn rpc_stream(&mut self, operation: &str) -> io::Result<ReplyReader<'_>> {
+        // wait for the late replies of abandoned RPCs, which would come first
+        let deadline = self.rpc_timeout().map(|timeout| Instant::now() + timeout);
+        while !self.outstanding.is_empty() {
+            if self
+                .outstanding
+                .iter()
+                .any(|id| !self.abandoned.contains(id))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Cannot stream a reply while other RPCs are outstanding",
+                ));
+            }
+            self.read_reply(deadline)?;
+        }
+        let pending = self.send_rpc(operation)?;
+        self.outstanding.retain(|id| *id != pending.message_id());
+        let mut reader = ReplyReader {
+            conn: self,
+            buffer: Vec::new(),
+            pos: 0,
+            done: false,
+        };
+        loop {
+            reader.read_start_tag()?;
+            let head = String::from_utf8_lossy(&reader.buffer);
+            match root_element(&head) {
+                // a reply without a message-id belongs to the only outstanding request
+                Some(("rpc-reply", _)) => match reply_message_id(&head) {
+                    Some(id) if id != pending.message_id() => {
+                        warn!("Ignoring reply with unknown message-id {}", id)
+                    }
+                    _ => return Ok(reader),
+                },
+                _ => warn!("Ignoring unexpected message: {}", head),
+            }
+            reader.skip_message()?;
+        }
+    }
+}
+
+/// Reader of a reply returned by [`Connection::rpc_stream`]
+///
+/// Reading returns the content of the `<rpc-reply>` message, without the
+/// framing, and reaches the end of file at the end of the message.
+pub struct ReplyReader<'a> {
+    conn: &'a mut Connection,
+    /// Data of the message not consumed yet
+    buffer: Vec<u8>,
+    pos: usize,
+    /// Whether the end of the message has been received
+    done: bool,
+}
+
+impl ReplyReader<'_> {
+    /// Receive data of the message until the start tag of its root element
+    /// is in the buffer, or the message ends
+    fn read_start_tag(&mut self) -> io::Result<()> {
+        while !self.done && root_element(&String::from_utf8_lossy(&self.buffer)).is_none() {
+            self.decode()?;
+        }
+        Ok(())
+    }
+
+    /// Read and discard the rest of the message, then start over with the next one
+    fn skip_message(&mut self) -> io::Result<()> {
+        loop {
+            let len = self.fill_buf()?.len();
+            if len == 0 {
+                break;
+            }
+            self.consume(len);
+        }
+        self.buffer.clear();
+        self.pos = 0;
+        self.done = false;
+        Ok(())
+    }
+
+    /// Append more data of the message to the buffer
+    fn decode(&mut self) -> io::Result<()> {
+        let len = self.buffer.len();
+        match self.conn.framer.decode_partial(&mut self.buffer) {
+            Ok(done) => self.done = done,
+            Err(e) => {
+                // the framer skips the rest of a rejected message by itself
+                if is_recoverable(&e) {
+                    self.done = true;
+                } else {
+                    self.conn.failed = true;
+                }
+                return Err(e);
+            }
+        }
+        if self.buffer.len() == len && !self.done {
+            self.fill()?;
+        }
+        Ok(())
+    }
+
+    /// Wrap the reader in a `quick_xml::Reader` to parse the reply as a
+    /// stream of XML events
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # use netconf_rs::Connection;
+    /// use quick_xml::events::Event;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// let mut reader = conn.rpc_stream("<get><filter type=\"subtree\"><interfaces \
+    ///     xmlns=\"urn:ietf:params:xml:ns:yang:ietf-interfaces\"/></filter></get>")?
+    ///     .xml_reader();
+    /// let mut buf = Vec::new();
+    /// let mut interfaces = 0;
+    /// loop {
+    ///     match reader.read_event_into(&mut buf).map_err(std::io::Error::other)? {
+    ///         Event::Start(e) if e.local_name().as_ref() == b"interface" => interfaces += 1,
+    ///         Event::Eof => break,
+    ///         _ => {}
+    ///     }
+    ///     buf.clear();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This method requires the `quick-xml` feature to be enabled.*
+    #[cfg(feature = "quick-xml")]
+    pub fn xml_reader(self) -> quick_xml::Reader<Self> {
+        quick_xml::Reader::from_reader(self)
+    }
+
+    /// Receive more data of the message from the transport
+    fn fill(&mut self) -> io::Result<()> {
+        let conn = &mut *self.conn;
+        let timeout = conn.rpc_timeout();
+        let framer = &mut conn.framer;
+        let res = read_with_timeout(&mut *conn.transport, timeout, |transport| {
+            framer.fill(transport)
+        });
+        match res {
+            Ok(_) => {
+                conn.last_activity = Instant::now();
+                Ok(())
+            }
+            // a timed out read keeps the data received so far
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => Err(e),
+            Err(e) => {
+                conn.failed = true;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Read for ReplyReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for ReplyReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos == self.buffer.len() && !self.done {
+            self.buffer.clear();
+            self.pos = 0;
+            self.decode()?;
+        }
+        Ok(&self.buffer[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.buffer.len());
+    }
+}
+
+impl Drop for ReplyReader<'_> {
+    fn drop(&mut self) {
+        // skip the rest of the message, so the next one can be read
+        if let Err(e) = self.skip_message() {
+            warn!("Failed to skip the rest of a streamed reply: {}", e);
+            self.conn.failed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::mock::MockTransport;
+
+    #[test]
+    fn stream_reply() {
+        let data = format!(
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>{}</data></rpc-reply>"#,
+            "<route><prefix>10.0.0.0/8</prefix></route>".repeat(10000)
+        );
+        let (transport, _) = MockTransport::new(&[
+            &data,
+            &data.replace("101", "102"),
+            r#"<rpc-reply message-id="103" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+
+        let mut reply = String::new();
+        conn.rpc_stream("<get/>")
+            .unwrap()
+            .read_to_string(&mut reply)
+            .unwrap();
+        assert_eq!(reply.trim(), data);
+
+        // the rest of the reply is skipped
+        let mut reader = conn.rpc_stream("<get/>").unwrap();
+        let mut start = [0; 10];
+        reader.read_exact(&mut start).unwrap();
+        assert_eq!(&start, b"<rpc-reply");
+        drop(reader);
+
+        assert!(conn.rpc("<commit/>").unwrap().contains("<ok/>"));
+        assert!(!conn.has_failed());
+    }
+
+    #[test]
+    fn skip_other_messages() {
+        let (transport, _) = MockTransport::new(&[
+            r#"<notification xmlns="urn:ietf:params:xml:ns:netconf:notification:1.0"><eventTime>2024-01-01T00:00:00Z</eventTime></notification>"#,
+            r#"<rpc-reply message-id="99" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+            r#"<?xml version="1.0"?><rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data>config</data></rpc-reply>"#,
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+        let mut reply = String::new();
+        conn.rpc_stream("<get/>")
+            .unwrap()
+            .read_to_string(&mut reply)
+            .unwrap();
+        assert!(reply.contains(r#"message-id="101""#));
+        assert!(reply.contains("<data>config</data>"));
+        assert!(!conn.has_failed());
+    }
+
+    #[cfg(feature = "quick-xml")]
+    #[test]
+    fn xml_events() {
+        use quick_xml::events::Event;
+
+        let (transport, _) = MockTransport::new(&[
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data><route/><route/><route/></data></rpc-reply>"#,
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+        let mut reader = conn.rpc_stream("<get/>").unwrap().xml_reader();
+        let mut buf = Vec::new();
+        let mut routes = 0;
+        loop {
+            match reader.read_event_into(&mut buf).unwrap() {
+                Event::Empty(e) if e.local_name().as_ref() == b"route" => routes += 1,
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        assert_eq!(routes, 3);
+    }
+}
@@ -0,0 +1,203 @@
+//! Authentication methods for the SSH transports
+//!
+//! Both `SSHTransport` (ssh2) and `RusshTransport` (russh) accept an
+//! [`Auth`], so credentials can be configured independently of the SSH backend.
+//!
+//! ## Examples
+//!
+//! ```
+//! use netconf_rs::transport::auth::Auth;
+//!
+//! let password = Auth::password("password");
+//! let key = Auth::key_file("/home/user/.ssh/id_ed25519", Some("passphrase"));
+//! let agent = Auth::Agent;
+//! // try the agent first, then fall back to a key file
+//! let any = Auth::Any(vec![Auth::Agent, key.clone()]);
+//! let otp = Auth::keyboard_interactive(|_instructions, prompts| {
+//!     prompts.iter().map(|_| String::from("123456")).collect()
+//! });
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A prompt of keyboard-interactive authentication
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    /// The label to show to the user, e.g. `Password: `
+    pub text: String,
+    /// Whether the response may be displayed while it is typed
+    pub echo: bool,
+}
+
+/// Callback answering keyboard-interactive prompts
+///
+/// It is called with the instructions of the server and the prompts, and
+/// returns one response for each prompt. It may be called several times, e.g.
+/// for a password followed by a one-time code.
+pub type PromptCallback = Arc<dyn Fn(&str, &[Prompt]) -> Vec<String> + Send + Sync>;
+
+/// How to authenticate to the SSH server
+#[derive(Clone)]
+pub enum Auth {
+    /// Password authentication
+    Password(String),
+    /// Public key authentication with a private key file
+    KeyFile {
+        /// Path to the private key (e.g., PEM, OpenSSH format)
+        path: PathBuf,
+        /// Passphrase of the private key, `None` if it is not encrypted
+        passphrase: Option<String>,
+    },
+    /// Public key authentication with a private key held in memory
+    Key {
+        /// The private key in PEM or OpenSSH format
+        private_key: String,
+        /// Passphrase of the private key, `None` if it is not encrypted
+        passphrase: Option<String>,
+    },
+    /// Public key authentication with an OpenSSH user certificate
+    Certificate {
+        /// The private key in PEM or OpenSSH format
+        private_key: String,
+        /// Passphrase of the private key, `None` if it is not encrypted
+        passphrase: Option<String>,
+        /// The certificate signed by the CA in OpenSSH format, as in `id_ed25519-cert.pub`
+        certificate: String,
+    },
+    /// Public key authentication with the identities of the ssh-agent at
+    /// `SSH_AUTH_SOCK`, tried in order
+    Agent,
+    /// Keyboard-interactive authentication, answering the prompts of the
+    /// server with the callback
+    KeyboardInteractive(PromptCallback),
+    /// Try the methods in order until the server accepts one
+    Any(Vec<Auth>),
+}
+
+impl Auth {
+    /// Password authentication
+    pub fn password(password: &str) -> Self {
+        Auth::Password(password.to_string())
+    }
+
+    /// Public key authentication with a private key file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the private key file
+    /// * `passphrase` - Passphrase of the private key (None if it has no passphrase)
+    pub fn key_file(path: impl Into<PathBuf>, passphrase: Option<&str>) -> Self {
+        Auth::KeyFile {
+            path: path.into(),
+            passphrase: passphrase.map(String::from),
+        }
+    }
+
+    /// Public key authentication with a private key held in memory
+    ///
+    /// # Arguments
+    ///
+    /// * `private_key` - The private key in PEM or OpenSSH format
+    /// * `passphrase` - Passphrase of the private key (None if it has no passphrase)
+    pub fn key(private_key: &str, passphrase: Option<&str>) -> Self {
+        Auth::Key {
+            private_key: private_key.to_string(),
+            passphrase: passphrase.map(String::from),
+        }
+    }
+
+    /// Public key authentication with an OpenSSH user certificate
+    ///
+    /// # Arguments
+    ///
+    /// * `private_key` - The private key in PEM or OpenSSH format
+    /// * `certificate` - The certificate in OpenSSH format
+    /// * `passphrase` - Passphrase of the private key (None if it has no passphrase)
+    pub fn certificate(private_key: &str, certificate: &str, passphrase: Option<&str>) -> Self {
+        Auth::Certificate {
+            private_key: private_key.to_string(),
+            passphrase: passphrase.map(String::from),
+            certificate: certificate.to_string(),
+        }
+    }
+
+    /// Public key authentication with an OpenSSH user certificate read from files
+    ///
+    /// # Arguments
+    ///
+    /// * `key_file` - Path to the private key file
+    /// * `certificate_file` - Path to the certificate, usually the key file with `-cert.pub` appended
+    /// * `passphrase` - Passphrase of the private key (None if it has no passphrase)
+    pub fn certificate_file(
+        key_file: &Path,
+        certificate_file: &Path,
+        passphrase: Option<&str>,
+    ) -> io::Result<Self> {
+        Ok(Self::certificate(
+            &fs::read_to_string(key_file)?,
+            &fs::read_to_string(certificate_file)?,
+            passphrase,
+        ))
+    }
+
+    /// Keyboard-interactive authentication answering prompts with `callback`
+    pub fn keyboard_interactive(
+        callback: impl Fn(&str, &[Prompt]) -> Vec<String> + Send + Sync + 'static,
+    ) -> Self {
+        Auth::KeyboardInteractive(Arc::new(callback))
+    }
+
+    /// The methods to try in order, with nested [`Auth::Any`] flattened
+    pub(crate) fn methods(&self) -> Vec<&Auth> {
+        match self {
+            Auth::Any(methods) => methods.iter().flat_map(Auth::methods).collect(),
+            auth => vec![auth],
+        }
+    }
+}
+
+/// Secrets are not printed
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::Password(_) => f.write_str("Password"),
+            Auth::KeyFile { path, .. } => f.debug_struct("KeyFile").field("path", path).finish(),
+            Auth::Key { .. } => f.write_str("Key"),
+            Auth::Certificate { .. } => f.write_str("Certificate"),
+            Auth::Agent => f.write_str("Agent"),
+            Auth::KeyboardInteractive(_) => f.write_str("KeyboardInteractive"),
+            Auth::Any(methods) => f.debug_tuple("Any").field(methods).finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_hides_secrets() {
+        let debug = format!(
+            "{:?} {:?} {:?}",
+            Auth::password("secret"),
+            Auth::key_file("/tmp/id_ed25519", Some("secret")),
+            Auth::key("secret", None)
+        );
+        assert!(!debug.contains("secret"));
+        assert!(debug.contains("/tmp/id_ed25519"));
+    }
+
+    #[test]
+    fn flatten_methods() {
+        let auth = Auth::Any(vec![
+            Auth::Agent,
+            Auth::Any(vec![Auth::password("a"), Auth::password("b")]),
+        ]);
+        let methods: Vec<String> = auth.methods().iter().map(|m| format!("{:?}", m)).collect();
+        assert_eq!(methods, ["Agent", "Password", "Password"]);
+    }
+}
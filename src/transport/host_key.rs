@@ -0,0 +1,247 @@
+//! Verification of SSH host keys
+//!
+//! Both SSH transports check the host key of the server against a
+//! [`HostKeyPolicy`] before sending any credentials. By default, the key has
+//! to be listed in `~/.ssh/known_hosts`, like with `StrictHostKeyChecking=yes`
+//! in OpenSSH.
+//!
+//! ## Examples
+//!
+//! ```
+//! use netconf_rs::transport::host_key::HostKeyPolicy;
+//!
+//! // add unknown hosts to ~/.ssh/known_hosts, reject changed keys
+//! let tofu = HostKeyPolicy::trust_on_first_use();
+//! // only accept a known key
+//! let pinned = HostKeyPolicy::Fingerprints(vec![
+//!     "SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8".to_string(),
+//! ]);
+//! ```
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Default SSH port, which is left out of `known_hosts` entries
+const SSH_PORT: u16 = 22;
+
+/// How the host key of the SSH server is verified
+#[derive(Debug, Clone)]
+pub enum HostKeyPolicy {
+    /// Only accept keys listed in this OpenSSH `known_hosts` file
+    KnownHosts(PathBuf),
+    /// Trust on first use: the key of a host missing from this `known_hosts`
+    /// file is accepted and added to it, a changed key is rejected
+    TrustOnFirstUse(PathBuf),
+    /// Only accept keys with these SHA-256 fingerprints, as printed by
+    /// `ssh-keygen -lf`, e.g. `SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8`
+    Fingerprints(Vec<String>),
+    /// Accept every key
+    ///
+    /// This is insecure: anyone on the path to the server can intercept the
+    /// session and the credentials.
+    AcceptAll,
+}
+
+impl Default for HostKeyPolicy {
+    fn default() -> Self {
+        Self::known_hosts()
+    }
+}
+
+impl HostKeyPolicy {
+    /// Only accept keys listed in `~/.ssh/known_hosts`
+    pub fn known_hosts() -> Self {
+        HostKeyPolicy::KnownHosts(default_known_hosts())
+    }
+
+    /// Trust on first use with `~/.ssh/known_hosts`
+    pub fn trust_on_first_use() -> Self {
+        HostKeyPolicy::TrustOnFirstUse(default_known_hosts())
+    }
+}
+
+/// `~/.ssh/known_hosts`
+fn default_known_hosts() -> PathBuf {
+    let home = env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .unwrap_or_default();
+    Path::new(&home).join(".ssh").join("known_hosts")
+}
+
+/// Split `host:port` into the host and port for `known_hosts` lookups
+pub(crate) fn host_and_port(addr: &str) -> (&str, u16) {
+    let (host, port) = match addr.rsplit_once(':') {
+        Some((host, port)) => match port.parse() {
+            Ok(port) => (host, port),
+            Err(_) => (addr, SSH_PORT),
+        },
+        None => (addr, SSH_PORT),
+    };
+    (host.trim_start_matches('[').trim_end_matches(']'), port)
+}
+
+/// The name of a host in `known_hosts`
+fn known_hosts_name(host: &str, port: u16) -> String {
+    if port == SSH_PORT {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+/// Check a SHA-256 fingerprint of `host` against the pinned fingerprints
+pub(crate) fn check_fingerprint(
+    host: &str,
+    fingerprint: &str,
+    fingerprints: &[String],
+) -> io::Result<()> {
+    let normalize = |fingerprint: &str| {
+        let fingerprint = fingerprint.trim();
+        let fingerprint = fingerprint.strip_prefix("SHA256:").unwrap_or(fingerprint);
+        fingerprint.trim_end_matches('=').to_string()
+    };
+    let fingerprint = normalize(fingerprint);
+    if fingerprints
+        .iter()
+        .any(|pinned| normalize(pinned) == fingerprint)
+    {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "Host key SHA256:{} of {} does not match any pinned fingerprint",
+                fingerprint, host
+            ),
+        ))
+    }
+}
+
+/// Error for a host missing from `known_hosts`
+pub(crate) fn unknown_host(host: &str, port: u16, path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!(
+            "Host key of {} is not in {}",
+            known_hosts_name(host, port),
+            path.display()
+        ),
+    )
+}
+
+/// Error for a host whose key differs from the one in `known_hosts`
+pub(crate) fn changed_host(host: &str, port: u16, path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!(
+            "Host key of {} does not match {}, possible man-in-the-middle attack",
+            known_hosts_name(host, port),
+            path.display()
+        ),
+    )
+}
+
+/// Append the key of `host` to `known_hosts`
+///
+/// # Arguments
+///
+/// * `key` - The public key in OpenSSH format, e.g. `ssh-ed25519 AAAA...`
+pub(crate) fn learn(path: &Path, host: &str, port: u16, key: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{} {}", known_hosts_name(host, port), key)
+}
+
+/// The public key blob of an SSH host key in OpenSSH format
+#[cfg(feature = "ssh2")]
+pub(crate) fn openssh_key(blob: &[u8]) -> io::Result<String> {
+    // the blob starts with the key type as an SSH string
+    let key_type = blob
+        .get(..4)
+        .map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize)
+        .and_then(|len| blob.get(4..4 + len))
+        .and_then(|key_type| std::str::from_utf8(key_type).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid host key"))?;
+    Ok(format!("{} {}", key_type, base64(blob)))
+}
+
+/// SHA-256 fingerprint of a host key from its hash, e.g. `SHA256:nThbg6kX...`
+#[cfg(feature = "ssh2")]
+pub(crate) fn fingerprint(sha256: &[u8]) -> String {
+    format!("SHA256:{}", base64(sha256).trim_end_matches('='))
+}
+
+/// Base64 encoding with padding
+#[cfg(feature = "ssh2")]
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut res = String::new();
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                res.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                res.push('=');
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_addr() {
+        assert_eq!(
+            host_and_port("sw1.example.net:830"),
+            ("sw1.example.net", 830)
+        );
+        assert_eq!(host_and_port("[2001:db8::1]:830"), ("2001:db8::1", 830));
+        assert_eq!(host_and_port("sw1"), ("sw1", 22));
+        assert_eq!(known_hosts_name("sw1", 22), "sw1");
+        assert_eq!(known_hosts_name("sw1", 830), "[sw1]:830");
+    }
+
+    #[test]
+    fn pinned_fingerprints() {
+        let pinned = ["SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8".to_string()];
+        assert!(check_fingerprint(
+            "sw1",
+            "nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8=",
+            &pinned
+        )
+        .is_ok());
+        assert_eq!(
+            check_fingerprint("sw1", "SHA256:AAAA", &pinned)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[cfg(feature = "ssh2")]
+    #[test]
+    fn encode_key() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(fingerprint(b"foobar"), "SHA256:Zm9vYmFy");
+        let blob = [&[0, 0, 0, 11][..], b"ssh-ed25519", &[1, 2, 3]].concat();
+        assert_eq!(
+            openssh_key(&blob).unwrap(),
+            format!("ssh-ed25519 {}", base64(&blob))
+        );
+        assert!(openssh_key(&[0, 0, 0, 11]).is_err());
+    }
+}
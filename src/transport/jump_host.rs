@@ -0,0 +1,67 @@
+//! SSH jump hosts
+//!
+//! Servers that are only reachable through a bastion are connected to
+//! through one or more jump hosts, like `ProxyJump` in OpenSSH: the
+//! transport logs into each jump host in turn and tunnels the SSH session
+//! to the next hop through a `direct-tcpip` channel. Every jump host has its
+//! own credentials and host key policy.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use netconf_rs::transport::auth::Auth;
+//! use netconf_rs::transport::jump_host::JumpHost;
+//! use netconf_rs::transport::ssh::{SSHConfig, SSHTransport};
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let config = SSHConfig::new().jump_host(JumpHost::new(
+//!     "bastion.example.net:22",
+//!     "jump",
+//!     Auth::Agent,
+//! ));
+//! let transport = SSHTransport::connect_with_config("10.0.0.1:830", "admin", "password", &config)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! *Note: This example requires the `ssh2` feature to be enabled.*
+
+use crate::transport::auth::Auth;
+use crate::transport::host_key::HostKeyPolicy;
+
+/// An SSH server to tunnel through
+#[derive(Debug, Clone)]
+pub struct JumpHost {
+    /// Address of the jump host in format "host:port"
+    pub addr: String,
+    /// SSH username on the jump host
+    pub user_name: String,
+    /// How to authenticate to the jump host
+    pub auth: Auth,
+    /// How the host key of the jump host is verified
+    pub host_key_policy: HostKeyPolicy,
+}
+
+impl JumpHost {
+    /// A jump host whose host key is verified against `~/.ssh/known_hosts`
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Address of the jump host in format "host:port" (e.g., "bastion.example.net:22")
+    /// * `user_name` - SSH username on the jump host
+    /// * `auth` - How to authenticate to the jump host
+    pub fn new(addr: &str, user_name: &str, auth: Auth) -> Self {
+        JumpHost {
+            addr: addr.to_string(),
+            user_name: user_name.to_string(),
+            auth,
+            host_key_policy: HostKeyPolicy::default(),
+        }
+    }
+
+    /// Set how the host key of the jump host is verified
+    pub fn host_key_policy(mut self, policy: HostKeyPolicy) -> Self {
+        self.host_key_policy = policy;
+        self
+    }
+}
@@ -0,0 +1,182 @@
+//! In-memory transport used by unit tests
+
+use crate::framing::Framer;
+use crate::transport::Transport;
+use std::collections::VecDeque;
+use std::io;
+use std::io::{Read, Write};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Replays canned server messages and records everything written
+pub(crate) struct MockTransport {
+    replies: VecDeque<String>,
+    /// The framed reply being read
+    current: VecDeque<u8>,
+    /// Written bytes not forming a complete message yet
+    pending: Vec<u8>,
+    written: Arc<Mutex<Vec<String>>>,
+    read_timeout: Option<Duration>,
+    /// The read timeout in effect when each message was written
+    write_timeouts: Arc<Mutex<Vec<Option<Duration>>>>,
+    /// Whether an async read is stuck at an empty reply
+    #[cfg(feature = "async")]
+    stalled: bool,
+}
+
+pub(crate) const HELLO: &str = r#"
+<hello xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <capabilities>
+        <capability>urn:ietf:params:netconf:base:1.0</capability>
+    </capabilities>
+    <session-id>4</session-id>
+</hello>"#;
+
+const END_OF_MESSAGE: &str = "]]>]]>";
+
+/// Play the server side of a session over `stream`: send [`HELLO`], read the
+/// hello of the client and one RPC, and answer it with `<ok/>`
+pub(crate) fn serve_one_rpc<S: Read + Write>(stream: &mut S) -> io::Result<()> {
+    let mut framer = Framer::new();
+    framer.write_message(stream, HELLO)?;
+    framer.read_message(stream)?;
+    framer.read_message(stream)?;
+    framer.write_message(
+        stream,
+        r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+    )
+}
+
+impl MockTransport {
+    /// Create a transport that answers the hello exchange and then
+    /// returns `replies` in order, using end-of-message framing
+    ///
+    /// An empty reply is a server that never answers: the read times out, or
+    /// panics if no read timeout is set, as it would block forever. An async
+    /// read stays pending instead, until the next write.
+    pub(crate) fn new(replies: &[&str]) -> (MockTransport, Arc<Mutex<Vec<String>>>) {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let mut queue = VecDeque::new();
+        queue.push_back(String::from(HELLO));
+        queue.extend(replies.iter().map(|r| String::from(*r)));
+        (
+            MockTransport {
+                replies: queue,
+                current: VecDeque::new(),
+                pending: Vec::new(),
+                written: written.clone(),
+                read_timeout: None,
+                write_timeouts: Arc::new(Mutex::new(Vec::new())),
+                #[cfg(feature = "async")]
+                stalled: false,
+            },
+            written,
+        )
+    }
+}
+
+impl MockTransport {
+    /// The read timeout in effect when each message was written
+    pub(crate) fn write_timeouts(&self) -> Arc<Mutex<Vec<Option<Duration>>>> {
+        self.write_timeouts.clone()
+    }
+}
+
+impl Read for MockTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.current.is_empty() {
+            match self.replies.pop_front() {
+                Some(reply) if reply.is_empty() => {
+                    assert!(
+                        self.read_timeout.is_some(),
+                        "Read without a timeout blocks forever"
+                    );
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "Read timed out"));
+                }
+                Some(reply) => self.current.extend((reply + END_OF_MESSAGE).as_bytes()),
+                None => return Ok(0),
+            }
+        }
+        self.current.read(buf)
+    }
+}
+
+impl Write for MockTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(pos) = self
+            .pending
+            .windows(END_OF_MESSAGE.len())
+            .position(|window| window == END_OF_MESSAGE.as_bytes())
+        {
+            let message = String::from_utf8_lossy(&self.pending[..pos]);
+            self.written
+                .lock()
+                .unwrap()
+                .push(String::from(message.trim()));
+            self.write_timeouts.lock().unwrap().push(self.read_timeout);
+            self.pending.drain(..pos + END_OF_MESSAGE.len());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for MockTransport {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.read_timeout = timeout;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncRead for MockTransport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.current.is_empty() && this.replies.front().is_some_and(String::is_empty) {
+            // never woken up, the future has to be dropped
+            this.stalled = true;
+            return Poll::Pending;
+        }
+        let bytes = Read::read(this, buf.initialize_unfilled())?;
+        buf.advance(bytes);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncWrite for MockTransport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.stalled {
+            this.replies.pop_front();
+            this.stalled = false;
+        }
+        Poll::Ready(Write::write(this, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
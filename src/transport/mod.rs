@@ -15,13 +15,51 @@
 //!   - Module path: `crate::transport::russh::RusshTransport`
 //!   - Provides async/await support with Tokio runtime
 //!
+//! - **TLS Transport** (`tls` module): NETCONF over TLS (RFC 7589) using the `rustls` library
+//!   - Requires the `tls` feature
+//!   - Module path: `crate::transport::tls::TlsTransport`
+//!   - Mutual X.509 certificate authentication
+//!
+//! - **TCP Transport** (`tcp` module): NETCONF over plain TCP, for simulators and testing
+//!   - Module path: `crate::transport::tcp::TcpTransport`
+//!
+//! - **Process Transport** (`process` module): NETCONF over the stdin and stdout of a command,
+//!   e.g. the OpenSSH client
+//!   - Module path: `crate::transport::process::ProcessTransport`
+//!
+//! Both SSH transports authenticate with an [`auth::Auth`]: a password, a
+//! private key, ssh-agent or keyboard-interactive.
+//! Host keys are verified according to a [`host_key::HostKeyPolicy`], by
+//! default against `~/.ssh/known_hosts`.
+//! Servers behind a bastion are reached through [`jump_host::JumpHost`]s.
+//!
 //! ## Transport Trait
 //!
-//! All transports implement the `Transport` trait which provides:
-//! - `read_xml()`: Read a complete XML message (delimited by `]]>]]>`)
-//! - `write_xml()`: Write an XML message with proper delimiters
+//! A transport is a bidirectional byte stream: the `Transport` trait builds
+//! on `std::io::Read` and `std::io::Write`. Splitting the stream into
+//! messages is left to the shared [`crate::framing::Framer`], so transports
+//! do not deal with `]]>]]>` delimiters or chunked framing.
+//!
+//! Byte streams that implement Tokio's `AsyncRead` and `AsyncWrite` are
+//! `AsyncTransport`s (requires the `async` feature), for use with
+//! `AsyncConnection`.
 
+use socket2::{Domain, Protocol, Socket, Type};
 use std::io;
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncWrite};
+
+#[cfg(any(feature = "ssh2", feature = "russh"))]
+pub mod auth;
+#[cfg(any(feature = "ssh2", feature = "russh"))]
+pub mod host_key;
+#[cfg(any(feature = "ssh2", feature = "russh"))]
+pub mod jump_host;
 
 #[cfg(feature = "ssh2")]
 pub mod ssh;
@@ -29,77 +67,309 @@ pub mod ssh;
 #[cfg(feature = "russh")]
 pub mod russh;
 
+#[cfg(feature = "tls")]
+pub mod tls;
+
+pub mod process;
+pub mod tcp;
+
+#[cfg(test)]
+pub(crate) mod mock;
+
 /// Trait for NETCONF transport layer
 ///
 /// This trait defines the interface that all NETCONF transports must implement.
-/// It provides methods for reading and writing XML messages with the proper
-/// NETCONF message delimiters (`]]>]]>`).
+/// A transport carries the bytes of a NETCONF session in both directions;
+/// message framing is handled by the connection.
 ///
 /// The trait is designed to be transport-agnostic, allowing different protocols
 /// (SSH, TCP, etc.) to be used for NETCONF communication.
 ///
 /// # Required Methods
 ///
-/// - `read_xml()`: Read a complete XML message from the transport
-/// - `write_xml()`: Write an XML message to the transport
+/// - `read()`: Read bytes from the transport, `Ok(0)` once it is closed
+/// - `write()` and `flush()`: Write bytes to the transport
 ///
 /// # Examples
 ///
 /// Implementing a custom transport:
 ///
 /// ```ignore
-/// use std::io;
+/// use std::io::{self, Read, Write};
+/// use std::net::TcpStream;
 /// use netconf_rs::transport::Transport;
 ///
-/// struct MyTransport;
+/// struct MyTransport(TcpStream);
 ///
-/// impl Transport for MyTransport {
-///     fn read_xml(&mut self) -> io::Result<String> {
-///         // Read until ]]>]]> delimiter
-///         Ok(String::from("<data/>"))
+/// impl Read for MyTransport {
+///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+///         self.0.read(buf)
 ///     }
+/// }
 ///
-///     fn write_xml(&mut self, data: &str) -> io::Result<()> {
-///         // Write data with ]]>]]> delimiter
-///         Ok(())
+/// impl Write for MyTransport {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.0.write(buf)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         self.0.flush()
 ///     }
 /// }
+///
+/// impl Transport for MyTransport {}
 /// ```
-pub trait Transport: Send {
-    /// Read a complete XML message from the transport
-    ///
-    /// This method should read data until the NETCONF message delimiter
-    /// (`]]>]]>`) is encountered, then return the message content (without the delimiter).
+pub trait Transport: Read + Write + Send {
+    /// Limit how long a read may wait for data
     ///
-    /// # Returns
+    /// When the timeout expires before any data has arrived, `read()` fails
+    /// with `io::ErrorKind::TimedOut`. `None` waits forever.
     ///
-    /// A `Result` containing the XML message as a string, or an `io::Error`
-    ///
-    /// # Errors
-    ///
-    /// This method will return an error if:
-    /// - The underlying connection is closed
-    /// - Invalid UTF-8 data is received
-    /// - An I/O error occurs
-    fn read_xml(&mut self) -> io::Result<String>;
-
-    /// Write an XML message to the transport
-    ///
-    /// This method should write the provided XML data to the transport,
-    /// appending the NETCONF message delimiter (`]]>]]>`).
+    /// The default implementation returns `io::ErrorKind::Unsupported` for
+    /// any timeout other than `None`.
     ///
     /// # Arguments
     ///
-    /// * `data` - The XML message to write (without delimiter)
-    ///
-    /// # Returns
-    ///
-    /// A `Result` indicating success or an `io::Error`
-    ///
-    /// # Errors
-    ///
-    /// This method will return an error if:
-    /// - The underlying connection is closed
-    /// - An I/O error occurs during writing
-    fn write_xml(&mut self, data: &str) -> io::Result<()>;
+    /// * `timeout` - Maximum time to wait, or `None` to disable the timeout
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        match timeout {
+            None => Ok(()),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Read timeouts are not supported by this transport",
+            )),
+        }
+    }
+}
+
+/// Trait for asynchronous NETCONF transport layer
+///
+/// This is the asynchronous counterpart of [`Transport`], used by
+/// [`crate::async_connection::AsyncConnection`]. Every Tokio byte stream,
+/// e.g. a `TcpStream`, is an `AsyncTransport`. Implementations must run on
+/// the caller's Tokio runtime and never block it.
+///
+/// *Note: This trait requires the `async` feature to be enabled.*
+#[cfg(feature = "async")]
+pub trait AsyncTransport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+#[cfg(feature = "async")]
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AsyncTransport for T {}
+
+/// Delay before connecting to the next address while an attempt is pending,
+/// as recommended by RFC 8305
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Connect to the first reachable address `addr` resolves to
+pub(crate) fn connect_tcp(addr: &str, timeout: Option<Duration>) -> io::Result<TcpStream> {
+    connect_tcp_from(addr, timeout, None, None)
+}
+
+/// Connect to the first reachable address `addr` resolves to, from a local
+/// source address or network interface
+///
+/// The addresses are tried Happy Eyeballs style (RFC 8305): IPv6 and IPv4
+/// addresses alternate, and the next attempt starts when the previous one
+/// failed or has not succeeded within 250 ms. The first established
+/// connection wins. The timeout applies to the whole connect.
+///
+/// # Arguments
+///
+/// * `addr` - Address in format "host:port", the host may be a name or an IP address
+/// * `timeout` - Timeout for the connect, or `None` to wait for the system timeout
+/// * `source_address` - Local address to connect from, only addresses of its family are tried
+/// * `interface` - Network interface to connect through (Linux only)
+pub(crate) fn connect_tcp_from(
+    addr: &str,
+    timeout: Option<Duration>,
+    source_address: Option<IpAddr>,
+    interface: Option<&str>,
+) -> io::Result<TcpStream> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut addrs = interleave(
+        addr.to_socket_addrs()?
+            .filter(|socket_addr| {
+                source_address.map_or(true, |source| source.is_ipv6() == socket_addr.is_ipv6())
+            })
+            .collect(),
+    )
+    .into_iter()
+    .peekable();
+    if addrs.peek().is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("No address found for {}", addr),
+        ));
+    }
+
+    let (sender, results) = mpsc::channel();
+    let mut pending = 0;
+    let mut last_error = None;
+    loop {
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        if remaining == Some(Duration::ZERO) {
+            break;
+        }
+        if let Some(socket_addr) = addrs.next() {
+            let sender = sender.clone();
+            let interface = interface.map(String::from);
+            thread::spawn(move || {
+                let res = connect_socket(socket_addr, remaining, source_address, interface);
+                // the receiver is gone once another attempt succeeded
+                let _ = sender.send(res);
+            });
+            pending += 1;
+        } else if pending == 0 {
+            break;
+        }
+
+        let res = match (addrs.peek(), remaining) {
+            (Some(_), Some(remaining)) => {
+                results.recv_timeout(remaining.min(CONNECTION_ATTEMPT_DELAY))
+            }
+            (Some(_), None) => results.recv_timeout(CONNECTION_ATTEMPT_DELAY),
+            (None, Some(remaining)) => results.recv_timeout(remaining),
+            (None, None) => results
+                .recv()
+                .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match res {
+            Ok(Ok(tcp)) => return Ok(tcp),
+            Ok(Err(e)) => {
+                pending -= 1;
+                last_error = Some(e);
+            }
+            Err(_) => {}
+        }
+    }
+    Err(last_error.filter(|_| pending == 0).unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("Timed out connecting to {}", addr),
+        )
+    }))
+}
+
+/// Alternate between IPv6 and IPv4 addresses, starting with the family of
+/// the first address
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let Some(first) = addrs.first() else {
+        return addrs;
+    };
+    let (mut preferred, mut other): (Vec<_>, Vec<_>) = addrs
+        .iter()
+        .partition(|socket_addr| socket_addr.is_ipv6() == first.is_ipv6());
+    let mut res = Vec::with_capacity(addrs.len());
+    preferred.reverse();
+    other.reverse();
+    while let Some(socket_addr) = preferred.pop() {
+        res.push(socket_addr);
+        res.extend(other.pop());
+    }
+    res.extend(other.into_iter().rev());
+    res
+}
+
+/// Connect a single TCP socket
+fn connect_socket(
+    addr: SocketAddr,
+    timeout: Option<Duration>,
+    source_address: Option<IpAddr>,
+    interface: Option<String>,
+) -> io::Result<TcpStream> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if let Some(interface) = interface {
+        bind_interface(&socket, &interface)?;
+    }
+    if let Some(source_address) = source_address {
+        socket.bind(&SocketAddr::new(source_address, 0).into())?;
+    }
+    match timeout {
+        Some(timeout) => socket.connect_timeout(&addr.into(), timeout)?,
+        None => socket.connect(&addr.into())?,
+    }
+    Ok(socket.into())
+}
+
+#[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+fn bind_interface(socket: &Socket, interface: &str) -> io::Result<()> {
+    socket.bind_device(Some(interface.as_bytes()))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")))]
+fn bind_interface(_socket: &Socket, _interface: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Binding to a network interface is only supported on Linux",
+    ))
+}
+
+/// Run `read` with `timeout` set on the transport, and clear it afterwards
+///
+/// The timeout of an SSH session applies to every call, so a leftover
+/// timeout would also bound the next write and the SSH keepalives.
+pub(crate) fn read_with_timeout<T, R>(
+    transport: &mut T,
+    timeout: Option<Duration>,
+    read: impl FnOnce(&mut T) -> io::Result<R>,
+) -> io::Result<R>
+where
+    T: Transport + ?Sized,
+{
+    transport.set_read_timeout(timeout)?;
+    let res = read(transport);
+    if timeout.is_some() {
+        transport.set_read_timeout(None)?;
+    }
+    res
+}
+
+/// Sockets with a timeout report `WouldBlock` on some platforms
+pub(crate) fn timed_out(error: io::Error) -> io::Error {
+    if error.kind() == io::ErrorKind::WouldBlock {
+        io::Error::new(io::ErrorKind::TimedOut, "Read timed out")
+    } else {
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr, TcpListener};
+
+    #[test]
+    fn interleave_families() {
+        let v4 = |port| SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port);
+        let v6 = |port| SocketAddr::new(Ipv6Addr::LOCALHOST.into(), port);
+        assert_eq!(
+            interleave(vec![v6(1), v6(2), v6(3), v4(4), v4(5)]),
+            [v6(1), v4(4), v6(2), v4(5), v6(3)]
+        );
+        assert_eq!(
+            interleave(vec![v4(1), v4(2), v6(3), v6(4), v6(5)]),
+            [v4(1), v6(3), v4(2), v6(4), v6(5)]
+        );
+    }
+
+    #[test]
+    fn connect_by_name() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let source = Some(IpAddr::from(Ipv4Addr::LOCALHOST));
+        // localhost may resolve to ::1 first, where nothing listens
+        let tcp = connect_tcp_from(
+            &format!("localhost:{}", port),
+            Some(Duration::from_secs(5)),
+            source,
+            None,
+        )
+        .unwrap();
+        let (_, peer) = listener.accept().unwrap();
+        assert_eq!(tcp.local_addr().unwrap(), peer);
+
+        let source = Some(IpAddr::from(Ipv6Addr::LOCALHOST));
+        let err = connect_tcp_from(&format!("127.0.0.1:{}", port), None, source, None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
 }
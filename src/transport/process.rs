@@ -0,0 +1,198 @@
+//! Transport over the standard input and output of a child process
+//!
+//! This module speaks NETCONF over the stdin and stdout of a command, such as
+//! `ssh -s router netconf`. This reuses everything the command supports,
+//! e.g. the OpenSSH configuration with `ControlMaster`, `ProxyJump` or
+//! Kerberos authentication, which the built-in SSH transports lack.
+//!
+//! ## Module Path
+//!
+//! `crate::transport::process::ProcessTransport`
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! use netconf_rs::transport::process::ProcessTransport;
+//! use netconf_rs::Connection;
+//! use std::process::Command;
+//!
+//! # fn main() -> std::io::Result<()> {
+//! // same as ProcessTransport::ssh("admin@192.168.1.1")
+//! let mut command = Command::new("ssh");
+//! command.args(["-s", "admin@192.168.1.1", "netconf"]);
+//! let transport = ProcessTransport::spawn(command)?;
+//! let mut conn = Connection::new(transport)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::transport::Transport;
+use log::*;
+use std::io;
+use std::io::{Read, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Number of bytes read from the process at once
+const READ_SIZE: usize = 64 * 1024;
+
+/// NETCONF over the stdin and stdout of a child process
+///
+/// The standard error of the process is inherited, so prompts and errors of
+/// the command show up on the terminal. The process is killed when the
+/// transport is dropped.
+pub struct ProcessTransport {
+    child: Child,
+    stdin: ChildStdin,
+    /// Output of the process, read by a background thread
+    output: mpsc::Receiver<io::Result<Vec<u8>>>,
+    /// Output received but not read yet
+    pending: Vec<u8>,
+    pos: usize,
+    read_timeout: Option<Duration>,
+}
+
+impl ProcessTransport {
+    /// Spawn `command` and talk NETCONF over its stdin and stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to run; its stdin and stdout are replaced by pipes
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the initialized `ProcessTransport` or an
+    /// `io::Error` if the process could not be started
+    pub fn spawn(mut command: Command) -> io::Result<ProcessTransport> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+        let (sender, output) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = vec![0; READ_SIZE];
+            loop {
+                match stdout.read(&mut buf) {
+                    // the sender is dropped at the end of the output
+                    Ok(0) => break,
+                    Ok(len) => {
+                        if sender.send(Ok(buf[..len].to_vec())).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => {
+                        let _ = sender.send(Err(e));
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(ProcessTransport {
+            child,
+            stdin,
+            output,
+            pending: Vec::new(),
+            pos: 0,
+            read_timeout: None,
+        })
+    }
+
+    /// Connect with the OpenSSH client by running `ssh -s <destination> netconf`
+    ///
+    /// # Arguments
+    ///
+    /// * `destination` - A host, `user@host` or an alias from `~/.ssh/config`
+    pub fn ssh(destination: &str) -> io::Result<ProcessTransport> {
+        let mut command = Command::new("ssh");
+        command.args(["-s", destination, "netconf"]);
+        Self::spawn(command)
+    }
+}
+
+impl Read for ProcessTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.pending.len() {
+            let received = match self.read_timeout {
+                Some(timeout) => self.output.recv_timeout(timeout),
+                None => self
+                    .output
+                    .recv()
+                    .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(data) => {
+                    self.pending = data?;
+                    self.pos = 0;
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "Read timed out"))
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.pending.len() - self.pos);
+        buf[..len].copy_from_slice(&self.pending[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+impl Write for ProcessTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdin.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdin.flush()
+    }
+}
+
+impl Transport for ProcessTransport {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.read_timeout = timeout;
+        Ok(())
+    }
+}
+
+impl Drop for ProcessTransport {
+    fn drop(&mut self) {
+        if let Err(e) = self.child.kill() {
+            debug!("Failed to kill NETCONF process: {}", e);
+        }
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::transport::mock::HELLO;
+    use crate::Connection;
+
+    #[test]
+    fn process_session() {
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", r#"printf '%s]]>]]>%s]]>]]>' "$HELLO" "$REPLY"; cat >/dev/null"#])
+            .env("HELLO", HELLO)
+            .env(
+                "REPLY",
+                r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><ok/></rpc-reply>"#,
+            );
+        let mut conn = Connection::with_rpc_timeout(
+            ProcessTransport::spawn(command).unwrap(),
+            Some(Duration::from_millis(100)),
+        )
+        .unwrap();
+        assert!(conn.rpc("<commit/>").unwrap().contains("<ok/>"));
+        assert_eq!(
+            conn.rpc("<commit/>").unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+    }
+}
@@ -6,9 +6,12 @@
 //! ## Features
 //!
 //! - Pure Rust implementation (no C dependencies)
-//! - Async/await support with Tokio runtime
-//! - Password and key-based authentication
+//! - Async/await support with Tokio runtime: the `*_async` constructors create a
+//!   transport that runs on the caller's runtime and implements `AsyncTransport`
+//! - Host key verification against `known_hosts` or pinned fingerprints
+//! - Password, key-based, OpenSSH certificate, ssh-agent and keyboard-interactive authentication
 //! - Configurable inactivity timeout
+//! - SSH keepalives
 //!
 //! ## Module Path
 //!
@@ -48,6 +51,47 @@
 //! # }
 //! ```
 //!
+//! ### ssh-agent, falling back to an OpenSSH certificate
+//!
+//! ```no_run
+//! use netconf_rs::transport::auth::Auth;
+//! use netconf_rs::transport::russh::{RusshConfig, RusshTransport};
+//! use std::path::Path;
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let certificate = Auth::certificate_file(
+//!     Path::new("/home/user/.ssh/id_ed25519"),
+//!     Path::new("/home/user/.ssh/id_ed25519-cert.pub"),
+//!     None,
+//! )?;
+//! let transport = RusshTransport::connect_with_auth(
+//!     "192.168.1.1:830",
+//!     "admin",
+//!     &Auth::Any(vec![Auth::Agent, certificate]),
+//!     &RusshConfig::default()
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ### Inside an existing Tokio runtime
+//!
+//! ```no_run
+//! use netconf_rs::async_connection::AsyncConnection;
+//! use netconf_rs::transport::russh::{RusshConfig, RusshTransport};
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let transport = RusshTransport::connect_password_async(
+//!     "192.168.1.1:830",
+//!     "admin",
+//!     "password",
+//!     &RusshConfig::default()
+//! ).await?;
+//! let mut conn = AsyncConnection::new(transport).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ### With custom configuration
 //!
 //! ```no_run
@@ -68,15 +112,30 @@
 //! # }
 //! ```
 
+use crate::transport::auth::{Auth, Prompt, PromptCallback};
+use crate::transport::host_key::{
+    changed_host, check_fingerprint, host_and_port, learn, unknown_host, HostKeyPolicy,
+};
+use crate::transport::jump_host::JumpHost;
 use crate::transport::Transport;
-use memmem::{Searcher, TwoWaySearcher};
+use log::*;
 use russh::client;
-use russh::keys::{load_secret_key, PrivateKeyWithHashAlg};
-use russh::{Channel, ChannelMsg};
+use russh::client::KeyboardInteractiveAuthResponse;
+#[cfg(unix)]
+use russh::keys::agent::client::AgentClient;
+use russh::keys::{
+    check_known_hosts_path, decode_secret_key, load_secret_key, Algorithm, Certificate, HashAlg,
+    PrivateKey, PrivateKeyWithHashAlg, PublicKey,
+};
+use russh::ChannelStream;
 use std::io;
+use std::io::{Read, Write};
 use std::path::Path;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
 use tokio::runtime::Runtime;
 
 /// Configuration for SSH transport using russh library
@@ -96,12 +155,24 @@ use tokio::runtime::Runtime;
 pub struct RusshConfig {
     /// Timeout for inactivity
     pub inactivity_timeout: Option<Duration>,
+    /// Interval between SSH keepalive messages on an idle session
+    pub keepalive_interval: Option<Duration>,
+    /// Number of unanswered keepalives before the session is closed
+    pub keepalive_max: usize,
+    /// How the host key of the server is verified
+    pub host_key_policy: HostKeyPolicy,
+    /// Jump hosts to tunnel through, in order
+    pub jump_hosts: Vec<JumpHost>,
 }
 
 impl Default for RusshConfig {
     fn default() -> Self {
         Self {
             inactivity_timeout: Some(Duration::from_secs(30)),
+            keepalive_interval: None,
+            keepalive_max: 3,
+            host_key_policy: HostKeyPolicy::default(),
+            jump_hosts: Vec::new(),
         }
     }
 }
@@ -118,10 +189,38 @@ impl RusshConfig {
         self
     }
 
+    /// Send SSH keepalive messages when the session is idle for `interval`
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive_interval = Some(interval);
+        self
+    }
+
+    /// Set the number of unanswered keepalives before the session is closed
+    pub fn keepalive_max(mut self, max: usize) -> Self {
+        self.keepalive_max = max;
+        self
+    }
+
+    /// Set how the host key of the server is verified
+    ///
+    /// Defaults to the keys in `~/.ssh/known_hosts`.
+    pub fn host_key_policy(mut self, policy: HostKeyPolicy) -> Self {
+        self.host_key_policy = policy;
+        self
+    }
+
+    /// Tunnel through a jump host, after the jump hosts added before
+    pub fn jump_host(mut self, jump_host: JumpHost) -> Self {
+        self.jump_hosts.push(jump_host);
+        self
+    }
+
     /// Build the russh client config
     fn build_client_config(&self) -> client::Config {
         client::Config {
             inactivity_timeout: self.inactivity_timeout,
+            keepalive_interval: self.keepalive_interval,
+            keepalive_max: self.keepalive_max,
             ..<_>::default()
         }
     }
@@ -130,15 +229,20 @@ impl RusshConfig {
 /// NETCONF over SSH using russh library
 ///
 /// This struct provides an asynchronous NETCONF transport over SSH using the `russh` library.
-/// It manages the underlying SSH session and NETCONF channel, handling message framing
-/// with the `]]>]]>` delimiter.
+/// It manages the underlying SSH session and NETCONF channel; message framing is
+/// done by the connection.
 ///
 /// The transport automatically:
 /// - Establishes a TCP connection to the server
 /// - Performs the SSH handshake
 /// - Authenticates with the provided credentials (password or key)
 /// - Opens a NETCONF subsystem channel
-/// - Bridges async and sync worlds using a Tokio runtime
+///
+/// Transports created with the synchronous constructors own a private Tokio
+/// runtime and implement [`Transport`], for use with [`crate::Connection`].
+/// They must not be used from within another Tokio runtime. Transports created
+/// with the `*_async` constructors run on the caller's runtime and implement
+/// [`AsyncTransport`], for use with [`crate::async_connection::AsyncConnection`].
 ///
 /// # Examples
 ///
@@ -174,21 +278,61 @@ impl RusshConfig {
 /// # }
 /// ```
 pub struct RusshTransport {
-    runtime: Runtime,
-    channel: Channel<client::Msg>,
-    read_buffer: Vec<u8>,
+    runtime: Option<Runtime>,
+    stream: ChannelStream<client::Msg>,
+    read_timeout: Option<Duration>,
 }
 
-struct ClientHandler;
+struct ClientHandler {
+    host: String,
+    port: u16,
+    host_key_policy: HostKeyPolicy,
+}
+
+impl ClientHandler {
+    /// Check the host key of the server according to the policy
+    fn verify_host_key(&self, key: &PublicKey) -> io::Result<()> {
+        let (host, port) = (self.host.as_str(), self.port);
+        let (path, trust_on_first_use) = match &self.host_key_policy {
+            HostKeyPolicy::AcceptAll => return Ok(()),
+            HostKeyPolicy::Fingerprints(fingerprints) => {
+                let fingerprint = key.fingerprint(HashAlg::Sha256).to_string();
+                return check_fingerprint(host, &fingerprint, fingerprints);
+            }
+            HostKeyPolicy::KnownHosts(path) => (path, false),
+            HostKeyPolicy::TrustOnFirstUse(path) => (path, true),
+        };
+        match check_known_hosts_path(host, port, key, path) {
+            Ok(true) => Ok(()),
+            Ok(false) if trust_on_first_use => {
+                info!("Adding host key of {}:{} to {}", host, port, path.display());
+                let key = key.to_openssh().map_err(io::Error::other)?;
+                learn(path, host, port, &key)
+            }
+            Ok(false) => Err(unknown_host(host, port, path)),
+            Err(russh::keys::Error::KeyChanged { .. }) => Err(changed_host(host, port, path)),
+            Err(e) => Err(io::Error::other(format!(
+                "Failed to check the host key of {}: {}",
+                host, e
+            ))),
+        }
+    }
+}
 
 impl client::Handler for ClientHandler {
     type Error = russh::Error;
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &russh::keys::PublicKey,
+        server_public_key: &PublicKey,
     ) -> Result<bool, Self::Error> {
-        Ok(true) // Accept all server keys for now
+        match self.verify_host_key(server_public_key) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                warn!("{}", e);
+                Ok(false)
+            }
+        }
     }
 }
 
@@ -237,53 +381,24 @@ impl RusshTransport {
         password: &str,
         config: &RusshConfig,
     ) -> io::Result<RusshTransport> {
-        let runtime = Runtime::new().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to create runtime: {}", e),
-            )
-        })?;
-
-        let client_config = Arc::new(config.build_client_config());
-        let handler = ClientHandler;
-
-        let mut session = runtime
-            .block_on(client::connect(client_config, addr, handler))
-            .map_err(|e| {
-                io::Error::new(io::ErrorKind::Other, format!("Connection failed: {}", e))
-            })?;
-
-        let auth_result = runtime
-            .block_on(session.authenticate_password(user_name, password))
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Auth failed: {}", e)))?;
-
-        if !auth_result.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::PermissionDenied,
-                "Authentication failed",
-            ));
-        }
-
-        let channel = runtime
-            .block_on(session.channel_open_session())
-            .map_err(|e| {
-                io::Error::new(io::ErrorKind::Other, format!("Channel open failed: {}", e))
-            })?;
-
-        runtime
-            .block_on(channel.request_subsystem(true, "netconf"))
-            .map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("Subsystem request failed: {}", e),
-                )
-            })?;
+        let runtime = new_runtime()?;
+        let mut res = runtime.block_on(Self::connect_password_async(
+            addr, user_name, password, config,
+        ))?;
+        res.runtime = Some(runtime);
+        Ok(res)
+    }
 
-        Ok(RusshTransport {
-            runtime,
-            channel,
-            read_buffer: Vec::new(),
-        })
+    /// Connect to a NETCONF server using password authentication on the current Tokio runtime
+    ///
+    /// The returned transport implements [`AsyncTransport`] only.
+    pub async fn connect_password_async(
+        addr: &str,
+        user_name: &str,
+        password: &str,
+        config: &RusshConfig,
+    ) -> io::Result<RusshTransport> {
+        Self::connect_with_auth_async(addr, user_name, &Auth::password(password), config).await
     }
 
     /// Connect to a NETCONF server using key-based authentication with default configuration
@@ -350,90 +465,478 @@ impl RusshTransport {
         passphrase: Option<&str>,
         config: &RusshConfig,
     ) -> io::Result<RusshTransport> {
-        let runtime = Runtime::new().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to create runtime: {}", e),
-            )
-        })?;
-
-        let key = load_secret_key(key_file, passphrase)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Key load failed: {}", e)))?;
-
-        let client_config = Arc::new(config.build_client_config());
-        let handler = ClientHandler;
-
-        let mut session = runtime
-            .block_on(client::connect(client_config, addr, handler))
-            .map_err(|e| {
-                io::Error::new(io::ErrorKind::Other, format!("Connection failed: {}", e))
-            })?;
+        let runtime = new_runtime()?;
+        let mut res = runtime.block_on(Self::connect_key_async(
+            addr, user_name, key_file, passphrase, config,
+        ))?;
+        res.runtime = Some(runtime);
+        Ok(res)
+    }
 
-        let key_with_alg = PrivateKeyWithHashAlg::new(Arc::new(key), None);
-        let auth_result = runtime
-            .block_on(session.authenticate_publickey(user_name, key_with_alg))
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Auth failed: {}", e)))?;
+    /// Connect to a NETCONF server using key-based authentication on the current Tokio runtime
+    ///
+    /// The returned transport implements [`AsyncTransport`] only.
+    pub async fn connect_key_async(
+        addr: &str,
+        user_name: &str,
+        key_file: &Path,
+        passphrase: Option<&str>,
+        config: &RusshConfig,
+    ) -> io::Result<RusshTransport> {
+        let auth = Auth::key_file(key_file, passphrase);
+        Self::connect_with_auth_async(addr, user_name, &auth, config).await
+    }
 
-        if !auth_result.success() {
-            return Err(io::Error::new(
+    /// Connect to a NETCONF server with any authentication method
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Server address in format "host:port" (e.g., "192.168.1.1:830")
+    /// * `user_name` - SSH username
+    /// * `auth` - How to authenticate, e.g. with a private key or keyboard-interactive
+    /// * `config` - Russh configuration options
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the initialized `RusshTransport` or an `io::Error`
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use netconf_rs::transport::auth::Auth;
+    /// use netconf_rs::transport::russh::{RusshConfig, RusshTransport};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let transport = RusshTransport::connect_with_auth(
+    ///     "192.168.1.1:830",
+    ///     "admin",
+    ///     &Auth::keyboard_interactive(|_, prompts| {
+    ///         prompts.iter().map(|_| String::from("password")).collect()
+    ///     }),
+    ///     &RusshConfig::default()
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn connect_with_auth(
+        addr: &str,
+        user_name: &str,
+        auth: &Auth,
+        config: &RusshConfig,
+    ) -> io::Result<RusshTransport> {
+        let runtime = new_runtime()?;
+        let mut res =
+            runtime.block_on(Self::connect_with_auth_async(addr, user_name, auth, config))?;
+        res.runtime = Some(runtime);
+        Ok(res)
+    }
+
+    /// Connect to a NETCONF server with any authentication method on the current Tokio runtime
+    ///
+    /// The returned transport implements [`AsyncTransport`] only.
+    pub async fn connect_with_auth_async(
+        addr: &str,
+        user_name: &str,
+        auth: &Auth,
+        config: &RusshConfig,
+    ) -> io::Result<RusshTransport> {
+        let mut session = connect_session(addr, config).await?;
+        authenticate(&mut session, user_name, auth).await?;
+        open_netconf(&session).await
+    }
+}
+
+fn key_error(e: russh::keys::Error) -> io::Error {
+    io::Error::other(format!("Key load failed: {}", e))
+}
+
+fn auth_error(e: russh::Error) -> io::Error {
+    io::Error::other(format!("Auth failed: {}", e))
+}
+
+/// Authenticate the session with `auth`
+///
+/// The methods of [`Auth::Any`] are tried in order, the error of the last
+/// one is returned if none succeeds.
+async fn authenticate(
+    session: &mut client::Handle<ClientHandler>,
+    user_name: &str,
+    auth: &Auth,
+) -> io::Result<()> {
+    let mut res = Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "No authentication method",
+    ));
+    for method in auth.methods() {
+        res = match authenticate_with(session, user_name, method).await {
+            Ok(true) => return Ok(()),
+            Ok(false) => Err(io::Error::new(
                 io::ErrorKind::PermissionDenied,
                 "Authentication failed",
-            ));
+            )),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &res {
+            debug!("Authentication with {:?} failed: {}", method, e);
         }
+    }
+    res
+}
 
-        let channel = runtime
-            .block_on(session.channel_open_session())
-            .map_err(|e| {
-                io::Error::new(io::ErrorKind::Other, format!("Channel open failed: {}", e))
-            })?;
-
-        runtime
-            .block_on(channel.request_subsystem(true, "netconf"))
-            .map_err(|e| {
+/// Try a single authentication method, `Ok(false)` if the server rejected it
+async fn authenticate_with(
+    session: &mut client::Handle<ClientHandler>,
+    user_name: &str,
+    auth: &Auth,
+) -> io::Result<bool> {
+    match auth {
+        Auth::Password(password) => Ok(session
+            .authenticate_password(user_name, password)
+            .await
+            .map_err(auth_error)?
+            .success()),
+        Auth::KeyFile { path, passphrase } => {
+            let key = load_secret_key(path, passphrase.as_deref()).map_err(key_error)?;
+            authenticate_key(session, user_name, key).await
+        }
+        Auth::Key {
+            private_key,
+            passphrase,
+        } => {
+            let key = decode_secret_key(private_key, passphrase.as_deref()).map_err(key_error)?;
+            authenticate_key(session, user_name, key).await
+        }
+        Auth::Certificate {
+            private_key,
+            passphrase,
+            certificate,
+        } => {
+            let key = decode_secret_key(private_key, passphrase.as_deref()).map_err(key_error)?;
+            let certificate = Certificate::from_openssh(certificate).map_err(|e| {
                 io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("Subsystem request failed: {}", e),
+                    io::ErrorKind::InvalidData,
+                    format!("Certificate load failed: {}", e),
                 )
             })?;
+            Ok(session
+                .authenticate_openssh_cert(user_name, Arc::new(key), certificate)
+                .await
+                .map_err(auth_error)?
+                .success())
+        }
+        Auth::Agent => authenticate_agent(session, user_name).await,
+        Auth::KeyboardInteractive(callback) => {
+            authenticate_keyboard_interactive(session, user_name, callback).await
+        }
+        Auth::Any(_) => unreachable!("methods are flattened"),
+    }
+}
 
-        Ok(RusshTransport {
-            runtime,
-            channel,
-            read_buffer: Vec::new(),
-        })
+/// Signature hash to use with RSA keys, `None` for other keys
+async fn rsa_hash(
+    session: &client::Handle<ClientHandler>,
+    key: &PublicKey,
+) -> io::Result<Option<HashAlg>> {
+    if !matches!(key.algorithm(), Algorithm::Rsa { .. }) {
+        return Ok(None);
     }
+    Ok(session
+        .best_supported_rsa_hash()
+        .await
+        .map_err(auth_error)?
+        .flatten())
 }
 
-impl Transport for RusshTransport {
-    fn read_xml(&mut self) -> io::Result<String> {
-        let search = TwoWaySearcher::new("]]>]]>".as_bytes());
-        while search.search_in(&self.read_buffer).is_none() {
-            let msg = self
-                .runtime
-                .block_on(self.channel.wait())
-                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed"))?;
-
-            match msg {
-                ChannelMsg::Data { ref data } => {
-                    self.read_buffer.extend_from_slice(data);
-                }
-                _ => {}
+/// Public key authentication
+async fn authenticate_key(
+    session: &mut client::Handle<ClientHandler>,
+    user_name: &str,
+    key: PrivateKey,
+) -> io::Result<bool> {
+    let hash_alg = rsa_hash(session, key.public_key()).await?;
+    let key_with_alg = PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg);
+    Ok(session
+        .authenticate_publickey(user_name, key_with_alg)
+        .await
+        .map_err(auth_error)?
+        .success())
+}
+
+/// Public key authentication with the identities of the ssh-agent at `SSH_AUTH_SOCK`
+#[cfg(unix)]
+async fn authenticate_agent(
+    session: &mut client::Handle<ClientHandler>,
+    user_name: &str,
+) -> io::Result<bool> {
+    let mut agent = AgentClient::connect_env()
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to connect to ssh-agent: {}", e)))?;
+    let identities = agent
+        .request_identities()
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to list agent identities: {}", e)))?;
+    for key in identities {
+        let hash_alg = rsa_hash(session, &key).await?;
+        let comment = key.comment().to_string();
+        let res = session
+            .authenticate_publickey_with(user_name, key, hash_alg, &mut agent)
+            .await
+            .map_err(|e| io::Error::other(format!("Auth failed: {}", e)))?;
+        if res.success() {
+            return Ok(true);
+        }
+        debug!("Agent identity {} rejected", comment);
+    }
+    Ok(false)
+}
+
+#[cfg(not(unix))]
+async fn authenticate_agent(
+    _session: &mut client::Handle<ClientHandler>,
+    _user_name: &str,
+) -> io::Result<bool> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "ssh-agent authentication is only supported on Unix",
+    ))
+}
+
+/// Keyboard-interactive authentication, answering every round of prompts with `callback`
+async fn authenticate_keyboard_interactive(
+    session: &mut client::Handle<ClientHandler>,
+    user_name: &str,
+    callback: &PromptCallback,
+) -> io::Result<bool> {
+    let mut response = session
+        .authenticate_keyboard_interactive_start(user_name, None)
+        .await
+        .map_err(auth_error)?;
+    loop {
+        match response {
+            KeyboardInteractiveAuthResponse::Success => return Ok(true),
+            KeyboardInteractiveAuthResponse::Failure { .. } => return Ok(false),
+            KeyboardInteractiveAuthResponse::InfoRequest {
+                instructions,
+                prompts,
+                ..
+            } => {
+                let prompts: Vec<Prompt> = prompts
+                    .into_iter()
+                    .map(|prompt| Prompt {
+                        text: prompt.prompt,
+                        echo: prompt.echo,
+                    })
+                    .collect();
+                let responses = callback(&instructions, &prompts);
+                response = session
+                    .authenticate_keyboard_interactive_respond(responses)
+                    .await
+                    .map_err(auth_error)?;
             }
         }
+    }
+}
+
+/// Runtime of a transport created with a synchronous constructor
+fn sync_runtime(runtime: &Option<Runtime>) -> io::Result<&Runtime> {
+    runtime.as_ref().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Transport was created on an async runtime, use it with AsyncConnection",
+        )
+    })
+}
+
+fn new_runtime() -> io::Result<Runtime> {
+    Runtime::new().map_err(|e| io::Error::other(format!("Failed to create runtime: {}", e)))
+}
+
+/// Establish the SSH session, through the jump hosts of `config`
+async fn connect_session(
+    addr: &str,
+    config: &RusshConfig,
+) -> io::Result<client::Handle<ClientHandler>> {
+    let client_config = Arc::new(config.build_client_config());
+    let handler = |addr: &str, host_key_policy: &HostKeyPolicy| {
+        let (host, port) = host_and_port(addr);
+        ClientHandler {
+            host: host.to_string(),
+            port,
+            host_key_policy: host_key_policy.clone(),
+        }
+    };
+    let Some(first) = config.jump_hosts.first() else {
+        return client::connect(client_config, addr, handler(addr, &config.host_key_policy))
+            .await
+            .map_err(connection_error);
+    };
+
+    let mut session = client::connect(
+        client_config.clone(),
+        &first.addr,
+        handler(&first.addr, &first.host_key_policy),
+    )
+    .await
+    .map_err(connection_error)?;
+    let mut jump = first;
+    let hops = config.jump_hosts.iter().skip(1).map(Some).chain([None]);
+    for next in hops {
+        authenticate(&mut session, &jump.user_name, &jump.auth).await?;
+        let (next_addr, host_key_policy) = match next {
+            Some(next) => (next.addr.as_str(), &next.host_key_policy),
+            None => (addr, &config.host_key_policy),
+        };
+        let (host, port) = host_and_port(next_addr);
+        debug!("Tunnelling to {} through {}", next_addr, jump.addr);
+        let channel = session
+            .channel_open_direct_tcpip(host, port.into(), "127.0.0.1", 0)
+            .await
+            .map_err(|e| io::Error::other(format!("Tunnel through {} failed: {}", jump.addr, e)))?;
+        session = client::connect_stream(
+            client_config.clone(),
+            channel.into_stream(),
+            handler(next_addr, host_key_policy),
+        )
+        .await
+        .map_err(connection_error)?;
+        if let Some(next) = next {
+            jump = next;
+        }
+    }
+    Ok(session)
+}
+
+fn connection_error(e: russh::Error) -> io::Error {
+    io::Error::other(format!("Connection failed: {}", e))
+}
+
+/// Open the NETCONF subsystem on an authenticated session
+async fn open_netconf(session: &client::Handle<ClientHandler>) -> io::Result<RusshTransport> {
+    let channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| io::Error::other(format!("Channel open failed: {}", e)))?;
+
+    channel
+        .request_subsystem(true, "netconf")
+        .await
+        .map_err(|e| io::Error::other(format!("Subsystem request failed: {}", e)))?;
+
+    Ok(RusshTransport {
+        runtime: None,
+        stream: channel.into_stream(),
+        read_timeout: None,
+    })
+}
+
+impl Read for RusshTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let runtime = sync_runtime(&self.runtime)?;
+        let read = self.stream.read(buf);
+        match self.read_timeout {
+            // reading is cancellation safe, so nothing is lost on timeout
+            Some(timeout) => runtime.block_on(async {
+                tokio::time::timeout(timeout, read).await.map_err(|_| {
+                    io::Error::new(io::ErrorKind::TimedOut, "Timed out reading from channel")
+                })?
+            }),
+            None => runtime.block_on(read),
+        }
+    }
+}
+
+impl Write for RusshTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let runtime = sync_runtime(&self.runtime)?;
+        runtime.block_on(self.stream.write(buf))
+    }
 
-        let pos = search.search_in(&self.read_buffer).unwrap();
-        let resp = String::from_utf8(self.read_buffer[..pos].to_vec())
-            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid UTF-8 in response"))?;
-        // 6: ]]>]]>
-        self.read_buffer.drain(0..(pos + 6));
-        Ok(resp)
+    fn flush(&mut self) -> io::Result<()> {
+        let runtime = sync_runtime(&self.runtime)?;
+        runtime.block_on(self.stream.flush())
     }
+}
 
-    fn write_xml(&mut self, data: &str) -> io::Result<()> {
-        let message = format!("{}]]>]]>", data.trim());
-        self.runtime
-            .block_on(self.channel.data(message.as_bytes()))
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Write error: {}", e)))?;
+impl Transport for RusshTransport {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.read_timeout = timeout;
         Ok(())
     }
 }
+
+impl AsyncRead for RusshTransport {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for RusshTransport {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIHkXWvxcZ3NmKSNjopuj5I/i+s81/+y4gmTW3bmvupnT";
+    const OTHER_KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAICfzFb4hfuyv2KQf5DL2agHxqo/vyZGonIaA5KBEsz2b";
+
+    fn handler(host_key_policy: HostKeyPolicy) -> ClientHandler {
+        ClientHandler {
+            host: "sw1".to_string(),
+            port: 830,
+            host_key_policy,
+        }
+    }
+
+    #[test]
+    fn host_key_policies() {
+        let key = PublicKey::from_openssh(KEY).unwrap();
+        let other_key = PublicKey::from_openssh(OTHER_KEY).unwrap();
+        let dir =
+            std::env::temp_dir().join(format!("netconf-rs-known-hosts-{}", std::process::id()));
+        let path = dir.join("known_hosts");
+
+        let strict = handler(HostKeyPolicy::KnownHosts(path.clone()));
+        assert!(strict.verify_host_key(&key).is_err());
+        let tofu = handler(HostKeyPolicy::TrustOnFirstUse(path.clone()));
+        tofu.verify_host_key(&key).unwrap();
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("[sw1]:830 ssh-ed25519 "));
+        strict.verify_host_key(&key).unwrap();
+        assert!(strict.verify_host_key(&other_key).is_err());
+        assert!(tofu.verify_host_key(&other_key).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let pinned = handler(HostKeyPolicy::Fingerprints(vec![
+            "SHA256:6mRRye7iH2gRYSqUPEWvIQKFB8ydl2FkNFG1ImLOh1M".to_string(),
+        ]));
+        pinned.verify_host_key(&key).unwrap();
+        assert!(pinned.verify_host_key(&other_key).is_err());
+        handler(HostKeyPolicy::AcceptAll)
+            .verify_host_key(&other_key)
+            .unwrap();
+    }
+}
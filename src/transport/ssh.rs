@@ -6,9 +6,12 @@
 //! ## Features
 //!
 //! - Synchronous API (blocking I/O)
-//! - Password authentication
+//! - Host key verification against `known_hosts` or pinned fingerprints
+//! - Password, public key, ssh-agent and keyboard-interactive authentication
 //! - Configurable SSH algorithms (KEX, ciphers, MACs, compression)
-//! - Timeout support for connection and handshake
+//! - Connects to host names and IPv6 addresses, optionally from a given source address
+//! - Timeout support for connection, handshake and reads
+//! - SSH keepalives sent from a background thread
 //!
 //! ## Module Path
 //!
@@ -27,6 +30,23 @@
 //! # }
 //! ```
 //!
+//! ### Public key authentication
+//!
+//! ```no_run
+//! use netconf_rs::transport::auth::Auth;
+//! use netconf_rs::transport::ssh::{SSHConfig, SSHTransport};
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let transport = SSHTransport::connect_with_auth(
+//!     "192.168.1.1:830",
+//!     "admin",
+//!     &Auth::key_file("/home/user/.ssh/id_ed25519", None),
+//!     &SSHConfig::default()
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ### With custom configuration
 //!
 //! ```no_run
@@ -36,11 +56,12 @@
 //! # fn main() -> std::io::Result<()> {
 //! let config = SSHConfig::new()
 //!     .connect_timeout(Duration::from_secs(10))
+//!     .source_address("10.0.0.5".parse().unwrap())
 //!     .kex_algo("curve25519-sha256,diffie-hellman-group14-sha256")
 //!     .cipher_algo("chacha20-poly1305@openssh.com,aes256-gcm@openssh.com");
 //!
 //! let transport = SSHTransport::connect_with_config(
-//!     "192.168.1.1:830",
+//!     "sw1.example.net:830",
 //!     "admin",
 //!     "password",
 //!     &config
@@ -49,14 +70,28 @@
 //! # }
 //! ```
 
-use crate::transport::Transport;
-use memmem::{Searcher, TwoWaySearcher};
+use crate::transport::auth::{Auth, Prompt, PromptCallback};
+use crate::transport::host_key::{
+    changed_host, check_fingerprint, fingerprint, host_and_port, learn, openssh_key, unknown_host,
+    HostKeyPolicy,
+};
+use crate::transport::jump_host::JumpHost;
+use crate::transport::{connect_tcp_from, Transport};
+use log::*;
+#[cfg(unix)]
+use ssh2::BlockDirections;
 use std::io;
 use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::net::{IpAddr, TcpStream};
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc;
+use std::thread;
 use std::time::Duration;
 
-use ssh2::{Channel, Session};
+use ssh2::{Channel, CheckResult, KeyboardInteractivePrompt, KnownHostFileKind, Session};
 
 /// Configuration for SSH transport using ssh2 library
 ///
@@ -79,6 +114,10 @@ use ssh2::{Channel, Session};
 pub struct SSHConfig {
     /// Timeout for TCP connection
     pub connect_timeout: Option<Duration>,
+    /// Local address to connect from
+    pub source_address: Option<IpAddr>,
+    /// Network interface to connect through (Linux only)
+    pub bind_interface: Option<String>,
     /// Timeout for SSH handshake
     pub handshake_timeout: Option<Duration>,
     /// Preferred key exchange methods (comma-separated string)
@@ -91,18 +130,29 @@ pub struct SSHConfig {
     pub mac_algo: Option<String>,
     /// Preferred compression algorithms (comma-separated string)
     pub compression_algo: Option<String>,
+    /// Interval between SSH keepalive messages on an idle session
+    pub keepalive_interval: Option<Duration>,
+    /// How the host key of the server is verified
+    pub host_key_policy: HostKeyPolicy,
+    /// Jump hosts to tunnel through, in order
+    pub jump_hosts: Vec<JumpHost>,
 }
 
 impl Default for SSHConfig {
     fn default() -> Self {
         Self {
             connect_timeout: Some(Duration::from_secs(30)),
+            source_address: None,
+            bind_interface: None,
             handshake_timeout: Some(Duration::from_secs(30)),
             kex_algo: None,
             hostkey_algo: None,
             cipher_algo: None,
             mac_algo: None,
             compression_algo: None,
+            keepalive_interval: None,
+            host_key_policy: HostKeyPolicy::default(),
+            jump_hosts: Vec::new(),
         }
     }
 }
@@ -119,6 +169,23 @@ impl SSHConfig {
         self
     }
 
+    /// Connect from this local address, e.g. in a management VRF
+    ///
+    /// Only server addresses of the same family are tried.
+    pub fn source_address(mut self, addr: IpAddr) -> Self {
+        self.source_address = Some(addr);
+        self
+    }
+
+    /// Connect through this network interface, e.g. `eth0` or a VRF device
+    ///
+    /// This uses `SO_BINDTODEVICE`, which is only available on Linux and
+    /// usually requires `CAP_NET_RAW`.
+    pub fn bind_interface(mut self, interface: &str) -> Self {
+        self.bind_interface = Some(interface.to_string());
+        self
+    }
+
     /// Set the SSH handshake timeout
     pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
         self.handshake_timeout = Some(timeout);
@@ -164,13 +231,39 @@ impl SSHConfig {
         self.compression_algo = Some(algos.to_string());
         self
     }
+
+    /// Send SSH keepalive messages when the session is idle for `interval`
+    ///
+    /// libssh2 only sends keepalives on request, so a background thread is
+    /// started for every connection. Intervals are rounded to whole seconds.
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive_interval = Some(interval);
+        self
+    }
+
+    /// Set how the host key of the server is verified
+    ///
+    /// Defaults to the keys in `~/.ssh/known_hosts`.
+    pub fn host_key_policy(mut self, policy: HostKeyPolicy) -> Self {
+        self.host_key_policy = policy;
+        self
+    }
+
+    /// Tunnel through a jump host, after the jump hosts added before
+    ///
+    /// The connection timeout and source address apply to the connection to
+    /// the first jump host.
+    pub fn jump_host(mut self, jump_host: JumpHost) -> Self {
+        self.jump_hosts.push(jump_host);
+        self
+    }
 }
 
 /// NETCONF over SSH using ssh2 library
 ///
 /// This struct provides a synchronous NETCONF transport over SSH using the `ssh2` library.
-/// It manages the underlying SSH session and NETCONF channel, handling message framing
-/// with the `]]>]]>` delimiter.
+/// It manages the underlying SSH session and NETCONF channel; message framing is
+/// done by the connection.
 ///
 /// The transport automatically:
 /// - Establishes a TCP connection to the server
@@ -190,10 +283,10 @@ impl SSHConfig {
 /// # }
 /// ```
 pub struct SSHTransport {
-    #[allow(dead_code)]
     session: Session,
     channel: Channel,
-    read_buffer: Vec<u8>,
+    /// Stops the keepalive thread when dropped
+    _keepalive: Option<mpsc::Sender<()>>,
 }
 
 impl SSHTransport {
@@ -247,7 +340,7 @@ impl SSHTransport {
     /// # Errors
     ///
     /// This function will return an error if:
-    /// - The address is invalid
+    /// - The address cannot be resolved
     /// - TCP connection fails
     /// - SSH handshake fails
     /// - Authentication fails
@@ -273,83 +366,505 @@ impl SSHTransport {
     /// # Ok(())
     /// # }
     /// ```
-    /// Connect with custom configuration
     pub fn connect_with_config(
         addr: &str,
         user_name: &str,
         password: &str,
         config: &SSHConfig,
     ) -> io::Result<SSHTransport> {
-        let tcp = if let Some(timeout) = config.connect_timeout {
-            let socket_addr = addr.parse().map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("Invalid address: {}", e),
-                )
-            })?;
-            TcpStream::connect_timeout(&socket_addr, timeout)?
-        } else {
-            TcpStream::connect(addr)?
+        Self::connect_with_auth(addr, user_name, &Auth::password(password), config)
+    }
+
+    /// Connect to a NETCONF server with any authentication method
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Server address in format "host:port" (e.g., "192.168.1.1:830")
+    /// * `user_name` - SSH username
+    /// * `auth` - How to authenticate, e.g. with a private key or ssh-agent
+    /// * `config` - SSH configuration options
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the initialized `SSHTransport` or an `io::Error`
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use netconf_rs::transport::auth::Auth;
+    /// use netconf_rs::transport::ssh::{SSHConfig, SSHTransport};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let transport = SSHTransport::connect_with_auth(
+    ///     "192.168.1.1:830",
+    ///     "admin",
+    ///     &Auth::Agent,
+    ///     &SSHConfig::default()
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn connect_with_auth(
+        addr: &str,
+        user_name: &str,
+        auth: &Auth,
+        config: &SSHConfig,
+    ) -> io::Result<SSHTransport> {
+        let first_addr = config.jump_hosts.first().map_or(addr, |jump| &jump.addr);
+        let tcp = connect_tcp_from(
+            first_addr,
+            config.connect_timeout,
+            config.source_address,
+            config.bind_interface.as_deref(),
+        )?;
+        let mut sess = new_session(config)?;
+        sess.set_tcp_stream(tcp);
+        if !config.jump_hosts.is_empty() {
+            sess = connect_jump_hosts(sess, addr, config)?;
+        }
+        let (host, port) = host_and_port(addr);
+        Self::open(sess, user_name, auth, config, |sess| {
+            verify_host_key(sess, host, port, &config.host_key_policy)
+        })
+    }
+
+    /// Run the SSH handshake on a TCP connection, check the host key with
+    /// `verify_host_key` and authenticate
+    pub(crate) fn handshake(
+        tcp: TcpStream,
+        user_name: &str,
+        auth: &Auth,
+        config: &SSHConfig,
+        verify_host_key: impl FnOnce(&Session) -> io::Result<()>,
+    ) -> io::Result<SSHTransport> {
+        let mut sess = new_session(config)?;
+        sess.set_tcp_stream(tcp);
+        Self::open(sess, user_name, auth, config, verify_host_key)
+    }
+
+    /// Log into a session and open the NETCONF subsystem
+    fn open(
+        mut sess: Session,
+        user_name: &str,
+        auth: &Auth,
+        config: &SSHConfig,
+        verify_host_key: impl FnOnce(&Session) -> io::Result<()>,
+    ) -> io::Result<SSHTransport> {
+        login(&mut sess, user_name, auth, verify_host_key)?;
+        let mut channel = sess.channel_session()?;
+        channel.subsystem("netconf")?;
+        // the session is established, reads set their own timeout from now on
+        sess.set_timeout(0);
+        let keepalive = config
+            .keepalive_interval
+            .map(|interval| spawn_keepalive(sess.clone(), interval));
+        let res = SSHTransport {
+            session: sess,
+            channel,
+            _keepalive: keepalive,
+        };
+        Ok(res)
+    }
+}
+
+/// A new session with the algorithm preferences and handshake timeout of `config`
+fn new_session(config: &SSHConfig) -> io::Result<Session> {
+    let sess = Session::new()?;
+    // bounds every blocking call until the session is established, so a
+    // server that never answers can't block the handshake forever
+    sess.set_timeout(timeout_ms(config.handshake_timeout));
+
+    // Apply algorithm preferences
+    if let Some(ref kex) = config.kex_algo {
+        sess.method_pref(ssh2::MethodType::Kex, kex)?;
+    }
+    if let Some(ref hostkey) = config.hostkey_algo {
+        sess.method_pref(ssh2::MethodType::HostKey, hostkey)?;
+    }
+    if let Some(ref ciphers) = config.cipher_algo {
+        sess.method_pref(ssh2::MethodType::CryptCs, ciphers)?;
+        sess.method_pref(ssh2::MethodType::CryptSc, ciphers)?;
+    }
+    if let Some(ref macs) = config.mac_algo {
+        sess.method_pref(ssh2::MethodType::MacCs, macs)?;
+        sess.method_pref(ssh2::MethodType::MacSc, macs)?;
+    }
+    if let Some(ref compression) = config.compression_algo {
+        sess.method_pref(ssh2::MethodType::CompCs, compression)?;
+        sess.method_pref(ssh2::MethodType::CompSc, compression)?;
+    }
+    Ok(sess)
+}
+
+/// Run the SSH handshake, check the host key with `verify_host_key` and authenticate
+fn login(
+    sess: &mut Session,
+    user_name: &str,
+    auth: &Auth,
+    verify_host_key: impl FnOnce(&Session) -> io::Result<()>,
+) -> io::Result<()> {
+    sess.handshake()?;
+    verify_host_key(sess)?;
+    authenticate(sess, user_name, auth)?;
+    if sess.authenticated() {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Log into the jump hosts of `config` in turn, starting with `sess`
+/// connected to the first one
+///
+/// # Returns
+///
+/// A `Result` containing a session tunnelled to `addr`, before the handshake
+#[cfg(unix)]
+fn connect_jump_hosts(mut sess: Session, addr: &str, config: &SSHConfig) -> io::Result<Session> {
+    let hops = config.jump_hosts.iter().map(|jump| jump.addr.as_str());
+    for (jump, next) in config.jump_hosts.iter().zip(hops.skip(1).chain([addr])) {
+        let (host, port) = host_and_port(&jump.addr);
+        login(&mut sess, &jump.user_name, &jump.auth, |sess| {
+            verify_host_key(sess, host, port, &jump.host_key_policy)
+        })?;
+        let (next_host, next_port) = host_and_port(next);
+        debug!("Tunnelling to {} through {}", next, jump.addr);
+        let channel = sess.channel_direct_tcpip(next_host, next_port, None)?;
+        // libssh2 needs a socket, so the channel is relayed through a socket pair
+        let (local, remote) = UnixStream::pair()?;
+        spawn_relay(sess, channel, remote)?;
+        sess = new_session(config)?;
+        sess.set_tcp_stream(local);
+    }
+    Ok(sess)
+}
+
+#[cfg(not(unix))]
+fn connect_jump_hosts(_sess: Session, _addr: &str, _config: &SSHConfig) -> io::Result<Session> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Jump hosts are only supported on Unix with the ssh2 backend",
+    ))
+}
+
+/// Relay between a `direct-tcpip` channel and a socket until either is closed
+///
+/// The session is switched to non-blocking mode, since a blocking read would
+/// keep it locked and stall writes in the other direction. When neither
+/// direction can make progress, the relay waits in `poll(2)` on both sockets.
+#[cfg(unix)]
+fn spawn_relay(sess: Session, mut channel: Channel, mut socket: UnixStream) -> io::Result<()> {
+    socket.set_nonblocking(true)?;
+    sess.set_blocking(false);
+    thread::spawn(move || {
+        let mut to_channel = Vec::new();
+        let mut to_socket = Vec::new();
+        let mut buf = vec![0; 32 * 1024];
+        let res: io::Result<()> = loop {
+            let mut progress = false;
+            if to_channel.is_empty() {
+                match socket.read(&mut buf) {
+                    Ok(0) => break Ok(()),
+                    Ok(len) => to_channel.extend_from_slice(&buf[..len]),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => break Err(e),
+                }
+            }
+            if !to_channel.is_empty() {
+                match channel.write(&to_channel) {
+                    Ok(len) => {
+                        to_channel.drain(..len);
+                        progress = true;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => break Err(e),
+                }
+            }
+            if to_socket.is_empty() {
+                match channel.read(&mut buf) {
+                    Ok(0) if channel.eof() => break Ok(()),
+                    Ok(len) => to_socket.extend_from_slice(&buf[..len]),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => break Err(e),
+                }
+            }
+            if !to_socket.is_empty() {
+                match socket.write(&to_socket) {
+                    Ok(len) => {
+                        to_socket.drain(..len);
+                        progress = true;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => break Err(e),
+                }
+            }
+            if !progress {
+                let mut socket_events = 0;
+                if to_channel.is_empty() {
+                    socket_events |= libc::POLLIN;
+                }
+                if !to_socket.is_empty() {
+                    socket_events |= libc::POLLOUT;
+                }
+                if let Err(e) = wait_relay(&sess, &socket, socket_events) {
+                    break Err(e);
+                }
+            }
         };
+        if let Err(e) = res {
+            debug!("Jump host tunnel closed: {}", e);
+        }
+        let _ = channel.close();
+    });
+    Ok(())
+}
 
-        let mut sess = Session::new()?;
+/// Wait until the relay socket is ready for `socket_events` or the session
+/// socket is ready for what libssh2 is blocked on
+///
+/// The wait is bounded by [`RELAY_MAX_WAIT`] so that a missed wakeup only
+/// delays the relay instead of stalling it.
+#[cfg(unix)]
+fn wait_relay(sess: &Session, socket: &UnixStream, socket_events: libc::c_short) -> io::Result<()> {
+    let session_events = match sess.block_directions() {
+        BlockDirections::Outbound | BlockDirections::Both => libc::POLLIN | libc::POLLOUT,
+        BlockDirections::Inbound | BlockDirections::None => libc::POLLIN,
+    };
+    let mut fds = [
+        libc::pollfd {
+            fd: socket.as_raw_fd(),
+            events: socket_events,
+            revents: 0,
+        },
+        libc::pollfd {
+            fd: sess.as_raw_fd(),
+            events: session_events,
+            revents: 0,
+        },
+    ];
+    let timeout = RELAY_MAX_WAIT.as_millis() as libc::c_int;
+    // SAFETY: `fds` is a valid array of `fds.len()` pollfd structs
+    let res = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) };
+    if res < 0 {
+        let e = io::Error::last_os_error();
+        if e.kind() != io::ErrorKind::Interrupted {
+            return Err(e);
+        }
+    }
+    Ok(())
+}
 
-        // Apply algorithm preferences
-        if let Some(ref kex) = config.kex_algo {
-            sess.method_pref(ssh2::MethodType::Kex, kex)?;
+/// Check the host key of the server according to `policy`
+fn verify_host_key(
+    sess: &Session,
+    host: &str,
+    port: u16,
+    policy: &HostKeyPolicy,
+) -> io::Result<()> {
+    let (key, _) = sess
+        .host_key()
+        .ok_or_else(|| io::Error::other("No host key"))?;
+    let (path, trust_on_first_use) = match policy {
+        HostKeyPolicy::AcceptAll => return Ok(()),
+        HostKeyPolicy::Fingerprints(fingerprints) => {
+            let hash = sess
+                .host_key_hash(ssh2::HashType::Sha256)
+                .ok_or_else(|| io::Error::other("No host key"))?;
+            return check_fingerprint(host, &fingerprint(hash), fingerprints);
         }
-        if let Some(ref hostkey) = config.hostkey_algo {
-            sess.method_pref(ssh2::MethodType::HostKey, hostkey)?;
+        HostKeyPolicy::KnownHosts(path) => (path, false),
+        HostKeyPolicy::TrustOnFirstUse(path) => (path, true),
+    };
+    let mut known_hosts = sess.known_hosts()?;
+    if path.exists() {
+        known_hosts.read_file(path, KnownHostFileKind::OpenSSH)?;
+    }
+    match known_hosts.check_port(host, port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::NotFound if trust_on_first_use => {
+            info!("Adding host key of {}:{} to {}", host, port, path.display());
+            learn(path, host, port, &openssh_key(key)?)
         }
-        if let Some(ref ciphers) = config.cipher_algo {
-            sess.method_pref(ssh2::MethodType::CryptCs, ciphers)?;
-            sess.method_pref(ssh2::MethodType::CryptSc, ciphers)?;
+        CheckResult::NotFound => Err(unknown_host(host, port, path)),
+        CheckResult::Mismatch => Err(changed_host(host, port, path)),
+        CheckResult::Failure => Err(io::Error::other(format!(
+            "Failed to check the host key of {}",
+            host
+        ))),
+    }
+}
+
+/// Authenticate the session with `auth`
+///
+/// The methods of [`Auth::Any`] are tried in order, the error of the last
+/// one is returned if none succeeds.
+fn authenticate(sess: &Session, user_name: &str, auth: &Auth) -> io::Result<()> {
+    let mut res = Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "No authentication method",
+    ));
+    for method in auth.methods() {
+        res = authenticate_with(sess, user_name, method);
+        match &res {
+            Ok(()) => break,
+            Err(e) => debug!("Authentication with {:?} failed: {}", method, e),
         }
-        if let Some(ref macs) = config.mac_algo {
-            sess.method_pref(ssh2::MethodType::MacCs, macs)?;
-            sess.method_pref(ssh2::MethodType::MacSc, macs)?;
+    }
+    res
+}
+
+fn authenticate_with(sess: &Session, user_name: &str, auth: &Auth) -> io::Result<()> {
+    match auth {
+        Auth::Password(password) => sess.userauth_password(user_name, password)?,
+        Auth::KeyFile { path, passphrase } => {
+            sess.userauth_pubkey_file(user_name, None, path, passphrase.as_deref())?
         }
-        if let Some(ref compression) = config.compression_algo {
-            sess.method_pref(ssh2::MethodType::CompCs, compression)?;
-            sess.method_pref(ssh2::MethodType::CompSc, compression)?;
+        Auth::Key {
+            private_key,
+            passphrase,
+        } => authenticate_key(sess, user_name, None, private_key, passphrase.as_deref())?,
+        Auth::Certificate {
+            private_key,
+            passphrase,
+            certificate,
+        } => authenticate_key(
+            sess,
+            user_name,
+            Some(certificate),
+            private_key,
+            passphrase.as_deref(),
+        )?,
+        Auth::Agent => authenticate_agent(sess, user_name)?,
+        Auth::KeyboardInteractive(callback) => {
+            sess.userauth_keyboard_interactive(user_name, &mut Prompter(callback))?
         }
+        Auth::Any(_) => unreachable!("methods are flattened"),
+    }
+    Ok(())
+}
 
-        sess.set_tcp_stream(tcp);
-        sess.handshake()?;
-
-        sess.userauth_password(user_name, password)?;
-        if sess.authenticated() {
-            let mut channel = sess.channel_session()?;
-            channel.subsystem("netconf")?;
-            let res = SSHTransport {
-                session: sess,
-                channel,
-                read_buffer: Vec::new(),
-            };
-            Ok(res)
-        } else {
-            Err(io::Error::last_os_error())
+/// Public key authentication with an in-memory key
+///
+/// A certificate is sent in place of the public key, which requires
+/// libssh2 1.11 or later.
+#[cfg(unix)]
+fn authenticate_key(
+    sess: &Session,
+    user_name: &str,
+    public_key: Option<&str>,
+    private_key: &str,
+    passphrase: Option<&str>,
+) -> io::Result<()> {
+    Ok(sess.userauth_pubkey_memory(user_name, public_key, private_key, passphrase)?)
+}
+
+#[cfg(not(unix))]
+fn authenticate_key(
+    _sess: &Session,
+    _user_name: &str,
+    _public_key: Option<&str>,
+    _private_key: &str,
+    _passphrase: Option<&str>,
+) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "In-memory keys are not supported by ssh2 on this platform, use a key file",
+    ))
+}
+
+/// Try the identities of the ssh-agent in order
+fn authenticate_agent(sess: &Session, user_name: &str) -> io::Result<()> {
+    let mut agent = sess.agent()?;
+    agent.connect()?;
+    agent.list_identities()?;
+    for identity in agent.identities()? {
+        match agent.userauth(user_name, &identity) {
+            Ok(()) => return Ok(()),
+            Err(e) => debug!("Agent identity {} rejected: {}", identity.comment(), e),
         }
     }
+    Err(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "No identity of the ssh-agent was accepted",
+    ))
 }
 
-impl Transport for SSHTransport {
-    fn read_xml(&mut self) -> io::Result<String> {
-        let mut buffer = [0u8; 128];
-        let search = TwoWaySearcher::new("]]>]]>".as_bytes());
-        while search.search_in(&self.read_buffer).is_none() {
-            let bytes = self.channel.read(&mut buffer)?;
-            self.read_buffer.extend(&buffer[..bytes]);
+/// Answers keyboard-interactive prompts with the callback of [`Auth::KeyboardInteractive`]
+struct Prompter<'a>(&'a PromptCallback);
+
+impl KeyboardInteractivePrompt for Prompter<'_> {
+    fn prompt<'a>(
+        &mut self,
+        _username: &str,
+        instructions: &str,
+        prompts: &[ssh2::Prompt<'a>],
+    ) -> Vec<String> {
+        let prompts: Vec<Prompt> = prompts
+            .iter()
+            .map(|prompt| Prompt {
+                text: prompt.text.to_string(),
+                echo: prompt.echo,
+            })
+            .collect();
+        (self.0)(instructions, &prompts)
+    }
+}
+
+/// Longest wait of an idle jump host tunnel before it checks both directions again
+#[cfg(unix)]
+const RELAY_MAX_WAIT: Duration = Duration::from_secs(1);
+
+/// Send keepalives until the returned sender is dropped
+///
+/// The session is locked during a blocking read, so keepalives only go out
+/// while the session is idle.
+fn spawn_keepalive(session: Session, interval: Duration) -> mpsc::Sender<()> {
+    let interval = interval.as_secs().clamp(1, u32::MAX as u64) as u32;
+    session.set_keepalive(true, interval);
+    let (stop, stopped) = mpsc::channel::<()>();
+    thread::spawn(move || {
+        let mut wait = Duration::from_secs(interval.into());
+        while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(wait) {
+            match session.keepalive_send() {
+                Ok(seconds) => wait = Duration::from_secs(seconds.max(1).into()),
+                Err(e) => {
+                    warn!("Failed to send SSH keepalive: {}", e);
+                    break;
+                }
+            }
         }
-        let pos = search.search_in(&self.read_buffer).unwrap();
-        let resp = String::from_utf8(self.read_buffer[..pos].to_vec()).unwrap();
-        // 6: ]]>]]>
-        self.read_buffer.drain(0..(pos + 6));
-        Ok(resp)
+    });
+    stop
+}
+
+impl Read for SSHTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.channel.read(buf)
     }
+}
 
-    fn write_xml(&mut self, data: &str) -> io::Result<()> {
-        write!(&mut self.channel, r#"{}]]>]]>"#, data.trim())?;
+impl Write for SSHTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.channel.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.channel.flush()
+    }
+}
+
+impl Transport for SSHTransport {
+    /// Applies to every blocking call of the SSH session, including writes
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.session.set_timeout(timeout_ms(timeout));
         Ok(())
     }
 }
+
+/// A timeout in milliseconds for `Session::set_timeout`
+fn timeout_ms(timeout: Option<Duration>) -> u32 {
+    // libssh2 treats 0 as "no timeout"
+    timeout.map_or(0, |timeout| {
+        timeout.as_millis().clamp(1, u32::MAX as u128) as u32
+    })
+}
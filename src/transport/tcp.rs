@@ -0,0 +1,123 @@
+//! Plain TCP transport
+//!
+//! This module carries NETCONF messages over a plain TCP connection, without
+//! any encryption or authentication. This is not a standard NETCONF
+//! transport, but is supported by simulators and by servers like netopeer2
+//! for testing, or behind a proxy that terminates SSH or TLS.
+//!
+//! ## Module Path
+//!
+//! `crate::transport::tcp::TcpTransport`
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! use netconf_rs::transport::tcp::TcpTransport;
+//! use netconf_rs::Connection;
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let transport = TcpTransport::connect("127.0.0.1:8300")?;
+//! let mut conn = Connection::new(transport)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::transport::{connect_tcp, timed_out, Transport};
+use std::io;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// NETCONF over plain TCP
+pub struct TcpTransport {
+    stream: TcpStream,
+}
+
+impl TcpTransport {
+    /// Connect to a NETCONF server over plain TCP
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Server address in format "host:port" (e.g., "127.0.0.1:8300")
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the initialized `TcpTransport` or an `io::Error`
+    pub fn connect(addr: &str) -> io::Result<TcpTransport> {
+        Self::connect_timeout(addr, Some(Duration::from_secs(30)))
+    }
+
+    /// Connect to a NETCONF server over plain TCP with a connection timeout
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Server address in format "host:port" (e.g., "127.0.0.1:8300")
+    /// * `timeout` - Timeout for the TCP connection, or `None` to use the system default
+    pub fn connect_timeout(addr: &str, timeout: Option<Duration>) -> io::Result<TcpTransport> {
+        Self::from_stream(connect_tcp(addr, timeout)?)
+    }
+
+    /// Use an established TCP connection
+    pub fn from_stream(stream: TcpStream) -> io::Result<TcpTransport> {
+        stream.set_nodelay(true)?;
+        Ok(TcpTransport { stream })
+    }
+}
+
+impl Read for TcpTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf).map_err(timed_out)
+    }
+}
+
+impl Write for TcpTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Transport for TcpTransport {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::Framer;
+    use crate::transport::mock::serve_one_rpc;
+    use crate::Connection;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn tcp_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            serve_one_rpc(&mut stream).unwrap();
+            // keep the connection open until the client is done
+            let mut framer = Framer::new();
+            while framer.read_message(&mut stream).is_ok() {}
+        });
+
+        let mut conn = Connection::with_rpc_timeout(
+            TcpTransport::connect(&addr).unwrap(),
+            Some(Duration::from_millis(100)),
+        )
+        .unwrap();
+        assert!(conn.rpc("<commit/>").unwrap().contains("<ok/>"));
+        assert_eq!(
+            conn.rpc("<commit/>").unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        drop(conn);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,610 @@
+//! TLS transport using rustls library
+//!
+//! This module provides NETCONF over TLS with mutual X.509 certificate
+//! authentication, as specified in
+//! [RFC 7589](https://datatracker.ietf.org/doc/html/rfc7589). The default port
+//! is 6513.
+//!
+//! ## Features
+//!
+//! - Pure Rust implementation using `rustls` with the `ring` crypto provider
+//! - Client certificate authentication, required by RFC 7589
+//! - Server verification against a CA bundle, pinned SHA-256 certificate
+//!   fingerprints, or both
+//! - Timeout support for connection, handshake and reads
+//!
+//! ## Module Path
+//!
+//! `crate::transport::tls::TlsTransport`
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! use netconf_rs::transport::tls::{TlsConfig, TlsTransport};
+//! use netconf_rs::Connection;
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let config = TlsConfig::new()
+//!     .ca_file("/etc/netconf/ca.pem")
+//!     .client_cert("/etc/netconf/client.pem", "/etc/netconf/client.key");
+//!
+//! let transport = TlsTransport::connect("192.168.1.1:6513", &config)?;
+//! let mut conn = Connection::new(transport)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::transport::{connect_tcp, timed_out, Transport};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{ring as provider, WebPkiSupportedAlgorithms};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore,
+    SignatureScheme, StreamOwned,
+};
+use std::io;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Configuration for TLS transport
+///
+/// A client certificate and at least one way to verify the server, a CA
+/// bundle or a pinned fingerprint, are required to connect.
+///
+/// # Example
+///
+/// ```
+/// use netconf_rs::transport::tls::TlsConfig;
+/// use std::time::Duration;
+///
+/// let config = TlsConfig::new()
+///     .connect_timeout(Duration::from_secs(10))
+///     .pin_fingerprint("3A:F1:...:09")
+///     .client_cert("client.pem", "client.key");
+/// ```
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// Timeout for TCP connection
+    pub connect_timeout: Option<Duration>,
+    /// Timeout for TLS handshake
+    pub handshake_timeout: Option<Duration>,
+    /// PEM file with the CA certificates trusted to issue the server certificate
+    pub ca_file: Option<PathBuf>,
+    /// SHA-256 fingerprints of the accepted server certificates, in hex
+    pub pinned_fingerprints: Vec<String>,
+    /// PEM file with the client certificate, followed by its intermediates
+    pub cert_file: Option<PathBuf>,
+    /// PEM file with the private key of the client certificate
+    pub key_file: Option<PathBuf>,
+    /// Name to verify the server certificate against, instead of the host of the address
+    pub server_name: Option<String>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Some(Duration::from_secs(30)),
+            handshake_timeout: Some(Duration::from_secs(30)),
+            ca_file: None,
+            pinned_fingerprints: Vec::new(),
+            cert_file: None,
+            key_file: None,
+            server_name: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    /// Create a new TLS configuration with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the TCP connection timeout
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the TLS handshake timeout
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = Some(timeout);
+        self
+    }
+
+    /// Verify the server certificate against the CA certificates in a PEM file
+    pub fn ca_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_file = Some(path.into());
+        self
+    }
+
+    /// Accept the server certificate with this SHA-256 fingerprint
+    ///
+    /// The fingerprint is given in hex, optionally separated by colons, as
+    /// printed by `openssl x509 -noout -fingerprint -sha256`. May be called
+    /// several times. Without a CA bundle, a pinned certificate is accepted
+    /// regardless of its issuer and names; with one, the certificate has to
+    /// pass both checks.
+    pub fn pin_fingerprint(mut self, fingerprint: &str) -> Self {
+        self.pinned_fingerprints.push(fingerprint.to_string());
+        self
+    }
+
+    /// Authenticate with the certificate chain and private key in PEM files
+    pub fn client_cert(
+        mut self,
+        cert_file: impl Into<PathBuf>,
+        key_file: impl Into<PathBuf>,
+    ) -> Self {
+        self.cert_file = Some(cert_file.into());
+        self.key_file = Some(key_file.into());
+        self
+    }
+
+    /// Verify the server certificate against `name` instead of the host of the address
+    pub fn server_name(mut self, name: &str) -> Self {
+        self.server_name = Some(name.to_string());
+        self
+    }
+
+    /// Build the rustls client configuration
+    fn client_config(&self) -> io::Result<ClientConfig> {
+        let provider = Arc::new(provider::default_provider());
+        let ca = match self.ca_file {
+            Some(ref path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in read_certs(path)? {
+                    roots.add(cert).map_err(invalid_data)?;
+                }
+                Some(
+                    WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                        .build()
+                        .map_err(invalid_data)?,
+                )
+            }
+            None => None,
+        };
+        let fingerprints = self
+            .pinned_fingerprints
+            .iter()
+            .map(|fingerprint| parse_fingerprint(fingerprint))
+            .collect::<io::Result<Vec<_>>>()?;
+        let verifier: Arc<dyn ServerCertVerifier> = match ca {
+            Some(ca) if fingerprints.is_empty() => ca,
+            _ if ca.is_none() && fingerprints.is_empty() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "No CA file or pinned fingerprint to verify the server with",
+                ))
+            }
+            ca => Arc::new(PinnedVerifier {
+                ca,
+                fingerprints,
+                algorithms: provider.signature_verification_algorithms,
+            }),
+        };
+
+        let (Some(cert_file), Some(key_file)) = (&self.cert_file, &self.key_file) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "NETCONF over TLS requires a client certificate",
+            ));
+        };
+        let certs = read_certs(cert_file)?;
+        let key = PrivateKeyDer::from_pem_file(key_file).map_err(|e| pem_error(key_file, e))?;
+
+        ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(invalid_data)?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier)
+            .with_client_auth_cert(certs, key)
+            .map_err(invalid_data)
+    }
+}
+
+/// Accepts server certificates by their SHA-256 fingerprint
+#[derive(Debug)]
+struct PinnedVerifier {
+    /// Also verify the certificate chain if set
+    ca: Option<Arc<WebPkiServerVerifier>>,
+    fingerprints: Vec<[u8; 32]>,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if let Some(ref ca) = self.ca {
+            ca.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+        }
+        let digest = ring::digest::digest(&ring::digest::SHA256, end_entity);
+        if self.fingerprints.iter().any(|f| f == digest.as_ref()) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// NETCONF over TLS using rustls library
+///
+/// This struct provides a synchronous NETCONF transport over TLS. It owns the
+/// TCP connection and the TLS session; message framing is done by the
+/// connection.
+///
+/// # Examples
+///
+/// ```no_run
+/// use netconf_rs::transport::tls::{TlsConfig, TlsTransport};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let config = TlsConfig::new()
+///     .pin_fingerprint("3a:f1:...:09")
+///     .client_cert("client.pem", "client.key");
+/// let transport = TlsTransport::connect("192.168.1.1:6513", &config)?;
+/// println!("Server certificate {:?}", transport.peer_fingerprint());
+/// # Ok(())
+/// # }
+/// ```
+pub struct TlsTransport {
+    stream: StreamOwned<ClientConnection, TcpStream>,
+}
+
+impl TlsTransport {
+    /// Connect to a NETCONF server over TLS
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Server address in format "host:port" (e.g., "192.168.1.1:6513")
+    /// * `config` - TLS configuration options
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the initialized `TlsTransport` or an `io::Error`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The configuration lacks a client certificate or a way to verify the server
+    /// - The certificate or key files cannot be read
+    /// - TCP connection fails
+    /// - TLS handshake fails, e.g. because the server certificate is not trusted
+    pub fn connect(addr: &str, config: &TlsConfig) -> io::Result<TlsTransport> {
+        let host = match config.server_name {
+            Some(ref name) => name.clone(),
+            None => host_of(addr).to_string(),
+        };
+        // fail early on a bad configuration
+        let client_config = config.client_config()?;
+        let tcp = connect_tcp(addr, config.connect_timeout)?;
+        Self::handshake(tcp, host, client_config, config)
+    }
+
+    /// Start a TLS session on an established TCP connection
+    ///
+    /// This is used for NETCONF Call Home, where the server connects to the
+    /// client. Unless [`TlsConfig::server_name`] is set, the server certificate
+    /// is verified against the IP address of the peer.
+    ///
+    /// # Arguments
+    ///
+    /// * `tcp` - The TCP connection to the server
+    /// * `config` - TLS configuration options
+    pub fn from_stream(tcp: TcpStream, config: &TlsConfig) -> io::Result<TlsTransport> {
+        let host = match config.server_name {
+            Some(ref name) => name.clone(),
+            None => tcp.peer_addr()?.ip().to_string(),
+        };
+        Self::handshake(tcp, host, config.client_config()?, config)
+    }
+
+    fn handshake(
+        mut tcp: TcpStream,
+        host: String,
+        client_config: ClientConfig,
+        config: &TlsConfig,
+    ) -> io::Result<TlsTransport> {
+        let server_name = ServerName::try_from(host).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid server name: {}", e),
+            )
+        })?;
+        let mut tls =
+            ClientConnection::new(Arc::new(client_config), server_name).map_err(invalid_data)?;
+
+        tcp.set_nodelay(true)?;
+        tcp.set_read_timeout(config.handshake_timeout)?;
+        tcp.set_write_timeout(config.handshake_timeout)?;
+        while tls.is_handshaking() {
+            tls.complete_io(&mut tcp).map_err(timed_out)?;
+        }
+        tcp.set_read_timeout(None)?;
+        tcp.set_write_timeout(None)?;
+        Ok(TlsTransport {
+            stream: StreamOwned::new(tls, tcp),
+        })
+    }
+
+    /// The SHA-256 fingerprint of the server certificate, in hex separated by colons
+    ///
+    /// Useful to find the value for [`TlsConfig::pin_fingerprint`].
+    pub fn peer_fingerprint(&self) -> Option<String> {
+        let cert = self.stream.conn.peer_certificates()?.first()?;
+        Some(fingerprint(cert))
+    }
+}
+
+/// SHA-256 fingerprint of a DER-encoded certificate, in hex separated by colons
+pub fn fingerprint(cert: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, cert)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn parse_fingerprint(fingerprint: &str) -> io::Result<[u8; 32]> {
+    let digits: String = fingerprint.chars().filter(|c| *c != ':').collect();
+    let mut res = [0; 32];
+    if digits.len() != 64 || !digits.is_ascii() {
+        return Err(invalid_fingerprint(fingerprint));
+    }
+    for (i, byte) in res.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16)
+            .map_err(|_| invalid_fingerprint(fingerprint))?;
+    }
+    Ok(res)
+}
+
+fn invalid_fingerprint(fingerprint: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid SHA-256 fingerprint: {}", fingerprint),
+    )
+}
+
+/// The host part of "host:port", without the brackets of an IPv6 address
+fn host_of(addr: &str) -> &str {
+    let host = match addr.rsplit_once(':') {
+        Some((host, port)) if port.parse::<u16>().is_ok() => host,
+        _ => addr,
+    };
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+fn read_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| pem_error(path, e))?;
+    if certs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("No certificate found in {}", path.display()),
+        ));
+    }
+    Ok(certs)
+}
+
+fn pem_error(path: &Path, error: rustls::pki_types::pem::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Failed to read {}: {}", path.display(), error),
+    )
+}
+
+fn invalid_data(error: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+impl Read for TlsTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf).map_err(timed_out)
+    }
+}
+
+impl Write for TlsTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Transport for TlsTransport {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.sock.set_read_timeout(timeout)
+    }
+}
+
+impl Drop for TlsTransport {
+    fn drop(&mut self) {
+        self.stream.conn.send_close_notify();
+        let _ = self.stream.conn.write_tls(&mut self.stream.sock);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::transport::mock::serve_one_rpc;
+    use crate::Connection;
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+    use rustls::server::WebPkiClientVerifier;
+    use rustls::{ServerConfig, ServerConnection};
+    use std::fs;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serve one NETCONF session answering one RPC with `<ok/>`
+    fn serve(listener: TcpListener, config: Arc<ServerConfig>) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            for tcp in listener.incoming() {
+                let tls = ServerConnection::new(config.clone()).unwrap();
+                let mut stream = StreamOwned::new(tls, tcp.unwrap());
+                // fails if the client rejected the server certificate
+                if serve_one_rpc(&mut stream).is_ok() {
+                    return;
+                }
+            }
+        })
+    }
+
+    /// Certificates of a CA, a server and a client
+    pub(crate) struct TestPki {
+        pub(crate) dir: PathBuf,
+        pub(crate) server: Arc<ServerConfig>,
+        pub(crate) server_der: CertificateDer<'static>,
+    }
+
+    impl TestPki {
+        /// Generate the certificates, writing the CA certificate and the
+        /// client certificate and key to `ca.pem`, `client.pem` and
+        /// `client.key` in a fresh directory
+        pub(crate) fn new(name: &str) -> TestPki {
+            let dir =
+                std::env::temp_dir().join(format!("netconf-rs-{}-{}", name, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+
+            let ca_key = KeyPair::generate().unwrap();
+            let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+            ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = ca_params.self_signed(&ca_key).unwrap();
+            let server_key = KeyPair::generate().unwrap();
+            let server = CertificateParams::new(vec![String::from("localhost")])
+                .unwrap()
+                .signed_by(&server_key, &ca, &ca_key)
+                .unwrap();
+            let client_key = KeyPair::generate().unwrap();
+            let client = CertificateParams::new(vec![String::from("netconf-client")])
+                .unwrap()
+                .signed_by(&client_key, &ca, &ca_key)
+                .unwrap();
+            fs::write(dir.join("ca.pem"), ca.pem()).unwrap();
+            fs::write(dir.join("client.pem"), client.pem()).unwrap();
+            fs::write(dir.join("client.key"), client_key.serialize_pem()).unwrap();
+
+            let mut roots = RootCertStore::empty();
+            roots.add(ca.der().clone()).unwrap();
+            let provider = Arc::new(provider::default_provider());
+            let client_verifier =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                    .build()
+                    .unwrap();
+            let server_config = ServerConfig::builder_with_provider(provider)
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_client_cert_verifier(client_verifier)
+                .with_single_cert(
+                    vec![server.der().clone()],
+                    PrivateKeyDer::try_from(server_key.serialize_der()).unwrap(),
+                )
+                .unwrap();
+            TestPki {
+                dir,
+                server: Arc::new(server_config),
+                server_der: server.der().clone(),
+            }
+        }
+
+        /// Client configuration trusting the CA
+        pub(crate) fn client_config(&self) -> TlsConfig {
+            TlsConfig::new()
+                .ca_file(self.dir.join("ca.pem"))
+                .server_name("localhost")
+                .client_cert(self.dir.join("client.pem"), self.dir.join("client.key"))
+        }
+    }
+
+    impl Drop for TestPki {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn mutual_auth() {
+        let pki = TestPki::new("tls");
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server_thread = serve(listener, pki.server.clone());
+
+        let config =
+            TlsConfig::new().client_cert(pki.dir.join("client.pem"), pki.dir.join("client.key"));
+        // no way to verify the server
+        assert_eq!(
+            TlsTransport::connect(&addr, &config).err().unwrap().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        // wrong pin
+        let wrong = config.clone().pin_fingerprint(&fingerprint(b"other"));
+        assert!(TlsTransport::connect(&addr, &wrong).is_err());
+
+        let transport = TlsTransport::connect(
+            &addr,
+            &pki.client_config()
+                .pin_fingerprint(&fingerprint(&pki.server_der)),
+        )
+        .unwrap();
+        assert_eq!(
+            transport.peer_fingerprint(),
+            Some(fingerprint(&pki.server_der))
+        );
+        let mut conn = Connection::new(transport).unwrap();
+        assert!(conn.rpc("<commit/>").unwrap().contains("<ok/>"));
+        drop(conn);
+        server_thread.join().unwrap();
+    }
+
+    #[test]
+    fn parse_fingerprints() {
+        let hex = "3A:F1".to_string() + &":00".repeat(30);
+        assert_eq!(parse_fingerprint(&hex).unwrap()[..2], [0x3a, 0xf1]);
+        assert_eq!(
+            parse_fingerprint(&hex.replace(':', "").to_lowercase()).unwrap(),
+            parse_fingerprint(&hex).unwrap()
+        );
+        assert!(parse_fingerprint("3A:F1").is_err());
+        assert_eq!(host_of("[::1]:6513"), "::1");
+        assert_eq!(host_of("router.example.com:6513"), "router.example.com");
+    }
+}
@@ -74,3 +74,67 @@ where
          Add --features serde-xml or --features quick-xml when building."
     );
 }
+
+/// Escape text for use in XML character data or attribute values
+pub fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&apos;"),
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+/// Resolve entity and character references and `CDATA` sections in XML character data
+pub fn unescape(s: &str) -> io::Result<String> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let mut res = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find(['&', '<']) {
+        res.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata
+                .find("]]>")
+                .ok_or_else(|| invalid("Unterminated CDATA section"))?;
+            res.push_str(&cdata[..end]);
+            rest = &cdata[end + 3..];
+            continue;
+        } else if rest.starts_with('<') {
+            return Err(invalid("Unexpected markup in character data"));
+        }
+        let end = rest
+            .find(';')
+            .ok_or_else(|| invalid("Unterminated entity reference"))?;
+        let entity = &rest[1..end];
+        match entity {
+            "amp" => res.push('&'),
+            "lt" => res.push('<'),
+            "gt" => res.push('>'),
+            "quot" => res.push('"'),
+            "apos" => res.push('\''),
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse().ok()
+                } else {
+                    None
+                };
+                let c = code
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| invalid(&format!("Unknown entity reference &{};", entity)))?;
+                res.push(c);
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    res.push_str(rest);
+    Ok(res)
+}
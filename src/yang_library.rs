@@ -0,0 +1,431 @@
+//! YANG library
+//!
+//! Retrieval of the `ietf-yang-library` data
+//! ([RFC 8525](https://datatracker.ietf.org/doc/html/rfc8525)), which lists the
+//! YANG modules, features and deviations implemented by the server. Servers
+//! following the NMDA architecture only announce this module in `<hello>`, so
+//! it is the only reliable way to discover what they support.
+//!
+//! Servers that only implement the older
+//! [RFC 7895](https://datatracker.ietf.org/doc/html/rfc7895) `modules-state`
+//! container are supported as well; their module list is converted to a
+//! single module set.
+
+use crate::error::is_rpc_error;
+use crate::xml::from_str;
+use crate::Connection;
+use log::*;
+use serde_derive::Deserialize;
+use std::io;
+
+/// Namespace of the `ietf-yang-library` YANG module
+pub const YANG_LIBRARY_NS: &str = "urn:ietf:params:xml:ns:yang:ietf-yang-library";
+
+#[derive(Debug, Deserialize)]
+struct YangLibraryReply {
+    data: YangLibraryData,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct YangLibraryData {
+    #[serde(rename = "yang-library")]
+    yang_library: Option<YangLibrary>,
+    #[serde(rename = "modules-state")]
+    modules_state: Option<ModulesState>,
+}
+
+/// Contents of the `/yang-library` container
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct YangLibrary {
+    /// Sets of modules, referenced by schemas
+    #[serde(rename = "module-set", default)]
+    pub module_sets: Vec<ModuleSet>,
+    /// Schemas, each the union of one or more module sets
+    #[serde(rename = "schema", default)]
+    pub schemas: Vec<Schema>,
+    /// Datastores and the schema each of them uses
+    #[serde(rename = "datastore", default)]
+    pub datastores: Vec<Datastore>,
+    /// Identifier that changes whenever the library contents change
+    #[serde(rename = "content-id")]
+    pub content_id: String,
+}
+
+impl YangLibrary {
+    /// Iterate over the implemented modules of all module sets
+    pub fn modules(&self) -> impl Iterator<Item = &Module> {
+        self.module_sets.iter().flat_map(|set| set.modules.iter())
+    }
+
+    /// Find an implemented module by name
+    pub fn module(&self, name: &str) -> Option<&Module> {
+        self.modules().find(|module| module.name == name)
+    }
+}
+
+/// A set of modules
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ModuleSet {
+    pub name: String,
+    /// Modules implemented by the server
+    #[serde(rename = "module", default)]
+    pub modules: Vec<Module>,
+    /// Modules whose definitions are only imported
+    #[serde(rename = "import-only-module", default)]
+    pub import_only_modules: Vec<ImportOnlyModule>,
+}
+
+/// An implemented module
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Module {
+    pub name: String,
+    /// Revision date, absent if the module has no revision statement
+    pub revision: Option<String>,
+    pub namespace: String,
+    /// URLs the module can be retrieved from
+    #[serde(default)]
+    pub location: Vec<String>,
+    #[serde(rename = "submodule", default)]
+    pub submodules: Vec<Submodule>,
+    /// Features supported by the server
+    #[serde(rename = "feature", default)]
+    pub features: Vec<String>,
+    /// Names of the modules containing deviations for this module
+    #[serde(rename = "deviation", default)]
+    pub deviations: Vec<String>,
+}
+
+/// A module whose definitions are only imported
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ImportOnlyModule {
+    pub name: String,
+    pub revision: Option<String>,
+    pub namespace: String,
+    #[serde(default)]
+    pub location: Vec<String>,
+    #[serde(rename = "submodule", default)]
+    pub submodules: Vec<Submodule>,
+}
+
+/// A submodule included by a module
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Submodule {
+    pub name: String,
+    pub revision: Option<String>,
+    #[serde(default)]
+    pub location: Vec<String>,
+}
+
+/// A schema built from module sets
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Schema {
+    pub name: String,
+    /// Names of the module sets the schema consists of
+    #[serde(rename = "module-set", default)]
+    pub module_sets: Vec<String>,
+}
+
+/// A datastore and its schema
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Datastore {
+    /// Datastore identity, e.g. `ds:running`
+    pub name: String,
+    /// Name of the schema used by the datastore
+    pub schema: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModulesState {
+    #[serde(rename = "module-set-id")]
+    module_set_id: String,
+    #[serde(default)]
+    module: Vec<LegacyModule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyModule {
+    name: String,
+    #[serde(default)]
+    revision: String,
+    schema: Option<String>,
+    namespace: String,
+    #[serde(default)]
+    feature: Vec<String>,
+    #[serde(default)]
+    deviation: Vec<LegacyDeviation>,
+    #[serde(rename = "conformance-type")]
+    conformance_type: String,
+    #[serde(default)]
+    submodule: Vec<LegacySubmodule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyDeviation {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacySubmodule {
+    name: String,
+    #[serde(default)]
+    revision: String,
+    schema: Option<String>,
+}
+
+fn revision(revision: String) -> Option<String> {
+    Some(revision).filter(|r| !r.trim().is_empty())
+}
+
+impl From<ModulesState> for YangLibrary {
+    fn from(state: ModulesState) -> YangLibrary {
+        let mut set = ModuleSet {
+            name: String::from("modules-state"),
+            modules: Vec::new(),
+            import_only_modules: Vec::new(),
+        };
+        for module in state.module {
+            let submodules = module
+                .submodule
+                .into_iter()
+                .map(|sub| Submodule {
+                    name: sub.name,
+                    revision: revision(sub.revision),
+                    location: sub.schema.into_iter().collect(),
+                })
+                .collect();
+            if module.conformance_type.trim() == "import" {
+                set.import_only_modules.push(ImportOnlyModule {
+                    name: module.name,
+                    revision: revision(module.revision),
+                    namespace: module.namespace,
+                    location: module.schema.into_iter().collect(),
+                    submodules,
+                });
+            } else {
+                set.modules.push(Module {
+                    name: module.name,
+                    revision: revision(module.revision),
+                    namespace: module.namespace,
+                    location: module.schema.into_iter().collect(),
+                    submodules,
+                    features: module.feature,
+                    deviations: module.deviation.into_iter().map(|d| d.name).collect(),
+                });
+            }
+        }
+        YangLibrary {
+            module_sets: vec![set],
+            schemas: Vec::new(),
+            datastores: Vec::new(),
+            content_id: state.module_set_id,
+        }
+    }
+}
+
+/// Build a `<get>` operation for a container of `ietf-yang-library`
+pub(crate) fn yang_library_request(container: &str) -> String {
+    format!(
+        r#"
+    <get>
+        <filter type="subtree">
+            <{} xmlns="{}"/>
+        </filter>
+    </get>"#,
+        container, YANG_LIBRARY_NS
+    )
+}
+
+fn parse_yang_library_data(resp: &str) -> io::Result<YangLibraryData> {
+    let reply: YangLibraryReply = from_str(resp.trim())?;
+    Ok(reply.data)
+}
+
+/// Interpret the reply to the `/yang-library` request, `None` if the legacy
+/// `/modules-state` container has to be retrieved instead
+pub(crate) fn yang_library_or_fallback(
+    resp: io::Result<String>,
+) -> io::Result<Option<YangLibrary>> {
+    match resp.and_then(|resp| parse_yang_library_data(&resp)) {
+        Ok(YangLibraryData {
+            yang_library: Some(library),
+            ..
+        }) => Ok(Some(library)),
+        Ok(_) => Ok(None),
+        Err(e) if is_rpc_error(&e) => {
+            debug!("Failed to get yang-library: {}", e);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Interpret the reply to the legacy `/modules-state` request
+pub(crate) fn modules_state_library(resp: &str) -> io::Result<YangLibrary> {
+    let data = parse_yang_library_data(resp)?;
+    data.modules_state.map(YangLibrary::from).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Server does not implement ietf-yang-library",
+        )
+    })
+}
+
+impl Connection {
+    /// Retrieve the YANG library of the server
+    ///
+    /// Reads `/yang-library` and falls back to the legacy `/modules-state`
+    /// container if the server does not implement it. A legacy library has a
+    /// single module set named `modules-state`, no schemas and no datastores,
+    /// and its `content_id` is the `module-set-id`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// # use netconf_rs::Connection;
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut conn = Connection::new(netconf_rs::transport::ssh::SSHTransport::connect(
+    /// #     "192.168.1.1:830", "admin", "password"
+    /// # )?)?;
+    /// let library = conn.get_yang_library()?;
+    /// for module in library.modules() {
+    ///     println!("{}@{:?} {:?}", module.name, module.revision, module.features);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// *Note: This example requires the `ssh2` feature to be enabled.*
+    pub fn get_yang_library(&mut self) -> io::Result<YangLibrary> {
+        let resp = self.rpc(&yang_library_request("yang-library"));
+        if let Some(library) = yang_library_or_fallback(resp)? {
+            return Ok(library);
+        }
+        modules_state_library(&self.rpc(&yang_library_request("modules-state"))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::mock::MockTransport;
+
+    #[test]
+    fn parse_yang_library() {
+        let (transport, _) = MockTransport::new(&[r#"
+<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <data>
+        <yang-library xmlns="urn:ietf:params:xml:ns:yang:ietf-yang-library"
+            xmlns:ds="urn:ietf:params:xml:ns:yang:ietf-datastores">
+            <module-set>
+                <name>config-modules</name>
+                <module>
+                    <name>ietf-interfaces</name>
+                    <revision>2018-02-20</revision>
+                    <namespace>urn:ietf:params:xml:ns:yang:ietf-interfaces</namespace>
+                    <feature>if-mib</feature>
+                    <deviation>example-deviations</deviation>
+                </module>
+                <module>
+                    <name>example-vlan</name>
+                    <namespace>urn:example:vlan</namespace>
+                    <submodule>
+                        <name>example-vlan-types</name>
+                        <revision>2023-05-01</revision>
+                    </submodule>
+                </module>
+                <import-only-module>
+                    <name>ietf-yang-types</name>
+                    <revision>2013-07-15</revision>
+                    <namespace>urn:ietf:params:xml:ns:yang:ietf-yang-types</namespace>
+                </import-only-module>
+            </module-set>
+            <schema>
+                <name>config-schema</name>
+                <module-set>config-modules</module-set>
+            </schema>
+            <datastore>
+                <name>ds:running</name>
+                <schema>config-schema</schema>
+            </datastore>
+            <content-id>75a43df9bd56b92aacc156a2958fbe12312fb285</content-id>
+        </yang-library>
+    </data>
+</rpc-reply>"#]);
+        let mut conn = Connection::new(transport).unwrap();
+        let library = conn.get_yang_library().unwrap();
+
+        assert_eq!(
+            library.module("ietf-interfaces"),
+            Some(&Module {
+                name: String::from("ietf-interfaces"),
+                revision: Some(String::from("2018-02-20")),
+                namespace: String::from("urn:ietf:params:xml:ns:yang:ietf-interfaces"),
+                location: Vec::new(),
+                submodules: Vec::new(),
+                features: vec![String::from("if-mib")],
+                deviations: vec![String::from("example-deviations")],
+            })
+        );
+        let vlan = library.module("example-vlan").unwrap();
+        assert_eq!(vlan.revision, None);
+        assert_eq!(vlan.submodules[0].name, "example-vlan-types");
+        assert_eq!(
+            library.module_sets[0].import_only_modules[0].name,
+            "ietf-yang-types"
+        );
+        assert_eq!(library.schemas[0].module_sets, vec!["config-modules"]);
+        assert_eq!(library.datastores[0].name, "ds:running");
+        assert_eq!(library.datastores[0].schema, "config-schema");
+    }
+
+    #[test]
+    fn fall_back_to_modules_state() {
+        let (transport, written) = MockTransport::new(&[
+            r#"<rpc-reply message-id="101" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0"><data/></rpc-reply>"#,
+            r#"
+<rpc-reply message-id="102" xmlns="urn:ietf:params:xml:ns:netconf:base:1.0">
+    <data>
+        <modules-state xmlns="urn:ietf:params:xml:ns:yang:ietf-yang-library">
+            <module-set-id>14e2ab5dc325f6d86f743e8d3ade233f1a61a899</module-set-id>
+            <module>
+                <name>example-vlan</name>
+                <revision></revision>
+                <namespace>urn:example:vlan</namespace>
+                <feature>qinq</feature>
+                <deviation>
+                    <name>example-vlan-deviations</name>
+                    <revision>2024-01-01</revision>
+                </deviation>
+                <conformance-type>implement</conformance-type>
+            </module>
+            <module>
+                <name>ietf-yang-types</name>
+                <revision>2013-07-15</revision>
+                <schema>https://example.com/ietf-yang-types.yang</schema>
+                <namespace>urn:ietf:params:xml:ns:yang:ietf-yang-types</namespace>
+                <conformance-type>import</conformance-type>
+            </module>
+        </modules-state>
+    </data>
+</rpc-reply>"#,
+        ]);
+        let mut conn = Connection::new(transport).unwrap();
+        let library = conn.get_yang_library().unwrap();
+
+        assert!(written.lock().unwrap()[2].contains("<modules-state"));
+        assert_eq!(
+            library.content_id,
+            "14e2ab5dc325f6d86f743e8d3ade233f1a61a899"
+        );
+        let vlan = library.module("example-vlan").unwrap();
+        assert_eq!(vlan.revision, None);
+        assert_eq!(vlan.features, vec!["qinq"]);
+        assert_eq!(vlan.deviations, vec!["example-vlan-deviations"]);
+        let import = &library.module_sets[0].import_only_modules[0];
+        assert_eq!(import.name, "ietf-yang-types");
+        assert_eq!(
+            import.location,
+            vec!["https://example.com/ietf-yang-types.yang"]
+        );
+    }
+}